@@ -0,0 +1,176 @@
+mod vals;
+
+use bilge::prelude::*;
+use embedded_hal::digital::{OutputPin, PinState};
+
+pub use vals::Channel;
+
+pub struct Cd4067<P, const N: usize = 1> {
+    en: [P; N],
+    s0: P,
+    s1: P,
+    s2: P,
+    s3: P,
+    enabled: Option<(usize, Channel)>,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum Error<E> {
+    Pin(E),
+    OutOfRange,
+}
+
+impl<P: OutputPin, const N: usize> Cd4067<P, N> {
+    pub fn new(en: [P; N], s0: P, s1: P, s2: P, s3: P) -> Self {
+        Self {
+            en,
+            s0,
+            s1,
+            s2,
+            s3,
+            enabled: None,
+        }
+    }
+
+    pub fn enabled(&self) -> Option<(usize, Channel)> {
+        self.enabled
+    }
+
+    pub fn enable(&mut self, chip: usize, channel: Channel) -> Result<(), Error<P::Error>> {
+        if chip >= N {
+            return Err(Error::OutOfRange);
+        }
+
+        self.disable()?;
+
+        #[bitsize(4)]
+        #[derive(FromBits)]
+        struct ChannelBits(bool, bool, bool, bool);
+
+        let bits = ChannelBits::from(u4::from(channel));
+        let bits = [bits.val_0(), bits.val_1(), bits.val_2(), bits.val_3()];
+        let [s0, s1, s2, s3] = bits.map(PinState::from);
+
+        self.s0.set_state(s0)?;
+        self.s1.set_state(s1)?;
+        self.s2.set_state(s2)?;
+        self.s3.set_state(s3)?;
+
+        self.en[chip].set_low()?;
+        self.enabled = Some((chip, channel));
+        Ok(())
+    }
+
+    pub fn disable(&mut self) -> Result<(), P::Error> {
+        for en in &mut self.en {
+            en.set_high()?;
+        }
+
+        self.enabled = None;
+        Ok(())
+    }
+}
+
+impl<E> From<E> for Error<E> {
+    fn from(value: E) -> Self {
+        Self::Pin(value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::convert::Infallible;
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    use embedded_hal::digital::ErrorType;
+
+    use super::*;
+
+    type Log = Rc<RefCell<Vec<(&'static str, bool)>>>;
+
+    struct Pin(&'static str, Log);
+
+    impl ErrorType for Pin {
+        type Error = Infallible;
+    }
+
+    impl OutputPin for Pin {
+        fn set_low(&mut self) -> Result<(), Self::Error> {
+            self.1.borrow_mut().push((self.0, false));
+            Ok(())
+        }
+
+        fn set_high(&mut self) -> Result<(), Self::Error> {
+            self.1.borrow_mut().push((self.0, true));
+            Ok(())
+        }
+    }
+
+    fn cascade(log: &Log) -> Cd4067<Pin, 2> {
+        let pin = |name| Pin(name, log.clone());
+        let en = [pin("en0"), pin("en1")];
+        Cd4067::new(en, pin("s0"), pin("s1"), pin("s2"), pin("s3"))
+    }
+
+    #[test]
+    fn enable_selects_channel_before_enabling_chip() {
+        let log = Log::default();
+        let mut cd4067 = cascade(&log);
+
+        cd4067.enable(1, Channel::C5).unwrap();
+
+        let expected = [
+            ("en0", true),
+            ("en1", true),
+            ("s0", true),
+            ("s1", false),
+            ("s2", true),
+            ("s3", false),
+            ("en1", false),
+        ];
+        assert_eq!(*log.borrow(), expected);
+        assert_eq!(cd4067.enabled(), Some((1, Channel::C5)));
+    }
+
+    #[test]
+    fn enable_breaks_before_switching_chips() {
+        let log = Log::default();
+        let mut cd4067 = cascade(&log);
+
+        cd4067.enable(0, Channel::C3).unwrap();
+        log.borrow_mut().clear();
+        cd4067.enable(1, Channel::C3).unwrap();
+
+        let log = log.borrow();
+        assert_eq!(log[..2], [("en0", true), ("en1", true)]);
+        assert_eq!(log.last(), Some(&("en1", false)));
+        assert!(!log.contains(&("en0", false)));
+    }
+
+    #[test]
+    fn enable_rejects_missing_chip() {
+        let log = Log::default();
+        let mut cd4067 = cascade(&log);
+
+        cd4067.enable(0, Channel::C1).unwrap();
+        log.borrow_mut().clear();
+
+        assert_eq!(cd4067.enable(2, Channel::C1), Err(Error::OutOfRange));
+        assert!(log.borrow().is_empty());
+        assert_eq!(cd4067.enabled(), Some((0, Channel::C1)));
+    }
+
+    #[test]
+    fn disable_releases_every_chip() {
+        let log = Log::default();
+        let mut cd4067 = cascade(&log);
+
+        cd4067.enable(1, Channel::C15).unwrap();
+        log.borrow_mut().clear();
+        cd4067.disable().unwrap();
+
+        assert_eq!(*log.borrow(), [("en0", true), ("en1", true)]);
+        assert_eq!(cd4067.enabled(), None);
+    }
+}
@@ -1,8 +1,8 @@
 use bilge::prelude::*;
-use defmt::Format;
 
 #[bitsize(4)]
-#[derive(FromBits, Clone, Copy, PartialEq, Eq, Format)]
+#[derive(FromBits, Clone, Copy, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub enum Channel {
     C0,
     C1,
@@ -0,0 +1,43 @@
+use fixed::types::{I32F0, I8F24};
+
+pub struct Calibration {
+    pub zero_counts: u16,
+    pub milliamps_per_count: I8F24,
+}
+
+impl Calibration {
+    pub fn milliamps(&self, count: u16) -> i32 {
+        let delta = i32::from(count) - i32::from(self.zero_counts);
+        let milliamps = self.milliamps_per_count.wide_mul(I32F0::from_num(delta));
+
+        milliamps.saturating_round().saturating_to_num()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use fixed_macro::types::I8F24;
+
+    const CALIBRATION: Calibration = Calibration {
+        zero_counts: 2048,
+        milliamps_per_count: I8F24!(2.01),
+    };
+
+    #[test]
+    fn converts_zero_offset() {
+        assert_eq!(CALIBRATION.milliamps(2048), 0);
+    }
+
+    #[test]
+    fn converts_full_operating_band() {
+        assert_eq!(CALIBRATION.milliamps(2048 + 75), 151);
+        assert_eq!(CALIBRATION.milliamps(2048 + 500), 1005);
+        assert_eq!(CALIBRATION.milliamps(2048 + 1200), 2412);
+    }
+
+    #[test]
+    fn converts_below_zero_offset() {
+        assert_eq!(CALIBRATION.milliamps(2048 - 100), -201);
+    }
+}
@@ -0,0 +1,330 @@
+use core::ops::RangeInclusive;
+
+use fixed::types::I8F24;
+use fixed_macro::types::I8F24;
+
+pub const MAX_WINDOW: usize = 9;
+
+pub trait Filter {
+    fn reset(&mut self);
+    fn update(&mut self, sample: I8F24) -> Option<I8F24>;
+}
+
+#[derive(Clone, Copy)]
+pub enum Config {
+    Average {
+        samples: i32,
+        noise: I8F24,
+    },
+    Median {
+        window: usize,
+    },
+    Exponential {
+        alpha: I8F24,
+    },
+    Kalman {
+        process_noise: I8F24,
+        measurement_noise: I8F24,
+    },
+}
+
+pub struct Average {
+    samples: i32,
+    noise: RangeInclusive<I8F24>,
+    average: I8F24,
+    count: i32,
+}
+
+pub struct Median {
+    window: usize,
+    samples: [I8F24; MAX_WINDOW],
+    len: usize,
+    next: usize,
+}
+
+pub struct Exponential {
+    alpha: I8F24,
+    value: Option<I8F24>,
+}
+
+pub struct Kalman {
+    process_noise: I8F24,
+    measurement_noise: I8F24,
+    estimate: Option<(I8F24, I8F24)>,
+}
+
+pub enum Stage {
+    Average(Average),
+    Median(Median),
+    Exponential(Exponential),
+    Kalman(Kalman),
+}
+
+impl Config {
+    pub const DEFAULT: Self = Self::Average {
+        samples: 5,
+        noise: I8F24!(0.1),
+    };
+}
+
+impl Average {
+    pub fn new(samples: i32, noise: I8F24) -> Self {
+        Self {
+            samples: samples.max(1),
+            noise: -noise.abs()..=noise.abs(),
+            average: I8F24::ZERO,
+            count: 0,
+        }
+    }
+}
+
+impl Filter for Average {
+    fn reset(&mut self) {
+        self.average = I8F24::ZERO;
+        self.count = 0;
+    }
+
+    fn update(&mut self, sample: I8F24) -> Option<I8F24> {
+        let delta = sample.saturating_sub(self.average);
+
+        self.count += 1;
+        self.average = self.average.saturating_add(delta / self.count);
+
+        let average = self.average;
+
+        if self.count < self.samples {
+            if self.count % 2 == 0 || self.noise.contains(&delta) {
+                return None;
+            }
+        } else {
+            self.count = 1;
+            self.average = sample;
+        }
+
+        Some(average)
+    }
+}
+
+impl Median {
+    pub fn new(window: usize) -> Self {
+        Self {
+            window: window.clamp(1, MAX_WINDOW),
+            samples: [I8F24::ZERO; MAX_WINDOW],
+            len: 0,
+            next: 0,
+        }
+    }
+}
+
+impl Filter for Median {
+    fn reset(&mut self) {
+        self.len = 0;
+        self.next = 0;
+    }
+
+    fn update(&mut self, sample: I8F24) -> Option<I8F24> {
+        self.samples[self.next] = sample;
+        self.next = (self.next + 1) % self.window;
+        self.len = (self.len + 1).min(self.window);
+
+        if self.len < self.window {
+            return None;
+        }
+
+        let mut sorted = self.samples;
+        let sorted = &mut sorted[..self.len];
+        sorted.sort_unstable();
+
+        Some(sorted[self.len / 2])
+    }
+}
+
+impl Exponential {
+    pub fn new(alpha: I8F24) -> Self {
+        Self {
+            alpha: alpha.clamp(I8F24::ZERO, I8F24::ONE),
+            value: None,
+        }
+    }
+}
+
+impl Filter for Exponential {
+    fn reset(&mut self) {
+        self.value = None;
+    }
+
+    fn update(&mut self, sample: I8F24) -> Option<I8F24> {
+        let value = match self.value {
+            Some(value) => {
+                value.saturating_add(self.alpha.saturating_mul(sample.saturating_sub(value)))
+            }
+            None => sample,
+        };
+
+        self.value = Some(value);
+        Some(value)
+    }
+}
+
+impl Kalman {
+    pub fn new(process_noise: I8F24, measurement_noise: I8F24) -> Self {
+        Self {
+            process_noise: process_noise.abs(),
+            measurement_noise: measurement_noise.abs().max(I8F24::DELTA),
+            estimate: None,
+        }
+    }
+}
+
+impl Filter for Kalman {
+    fn reset(&mut self) {
+        self.estimate = None;
+    }
+
+    fn update(&mut self, sample: I8F24) -> Option<I8F24> {
+        let (value, variance) = match self.estimate {
+            Some((value, variance)) => {
+                let variance = variance.saturating_add(self.process_noise);
+                let gain = variance.saturating_div(variance.saturating_add(self.measurement_noise));
+                let value = value.saturating_add(gain.saturating_mul(sample.saturating_sub(value)));
+
+                (value, (I8F24::ONE - gain).saturating_mul(variance))
+            }
+            None => (sample, self.measurement_noise),
+        };
+
+        self.estimate = Some((value, variance));
+        Some(value)
+    }
+}
+
+impl Stage {
+    pub fn new(config: Config) -> Self {
+        match config {
+            Config::Average { samples, noise } => Self::Average(Average::new(samples, noise)),
+            Config::Median { window } => Self::Median(Median::new(window)),
+            Config::Exponential { alpha } => Self::Exponential(Exponential::new(alpha)),
+            Config::Kalman {
+                process_noise,
+                measurement_noise,
+            } => Self::Kalman(Kalman::new(process_noise, measurement_noise)),
+        }
+    }
+}
+
+impl Filter for Stage {
+    fn reset(&mut self) {
+        match self {
+            Self::Average(f) => f.reset(),
+            Self::Median(f) => f.reset(),
+            Self::Exponential(f) => f.reset(),
+            Self::Kalman(f) => f.reset(),
+        }
+    }
+
+    fn update(&mut self, sample: I8F24) -> Option<I8F24> {
+        match self {
+            Self::Average(f) => f.update(sample),
+            Self::Median(f) => f.update(sample),
+            Self::Exponential(f) => f.update(sample),
+            Self::Kalman(f) => f.update(sample),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TRACE: [f64; 16] = [
+        1.62, 1.63, 1.61, 1.62, 1.64, 1.62, 2.40, 1.61, 1.63, 1.62, 1.35, 1.34, 1.36, 1.35, 1.35,
+        1.34,
+    ];
+
+    fn run(filter: &mut impl Filter, trace: &[f64]) -> Vec<Option<f64>> {
+        let update = |&v| filter.update(I8F24::from_num(v)).map(|v| v.to_num());
+        trace.iter().map(update).collect()
+    }
+
+    fn close(value: f64, expected: f64) -> bool {
+        (value - expected).abs() < 0.005
+    }
+
+    fn spread(values: &[f64]) -> f64 {
+        let max = values.iter().copied().fold(f64::MIN, f64::max);
+        let min = values.iter().copied().fold(f64::MAX, f64::min);
+        max - min
+    }
+
+    #[test]
+    fn average_holds_back_samples_within_noise() {
+        let mut average = Average::new(5, I8F24!(0.1));
+        let output = run(&mut average, &TRACE[..5]);
+
+        assert!(output[0].is_some());
+        assert_eq!(output[1..4], [None, None, None]);
+        assert!(close(output[4].unwrap(), 1.624));
+    }
+
+    #[test]
+    fn average_reports_steps_outside_noise() {
+        let mut average = Average::new(5, I8F24!(0.1));
+        let output = run(&mut average, &[1.62, 1.62, 1.35]);
+
+        assert_eq!(output[1], None);
+        assert!(close(output[2].unwrap(), 1.53));
+    }
+
+    #[test]
+    fn median_warms_up_then_rejects_spike() {
+        let mut median = Median::new(3);
+        let output = run(&mut median, &TRACE[4..9]);
+
+        assert_eq!(output[..2], [None, None]);
+        assert!(output[2..].iter().all(|v| v.is_some_and(|v| v <= 1.64)));
+    }
+
+    #[test]
+    fn exponential_converges_on_step() {
+        let mut exponential = Exponential::new(I8F24!(0.5));
+        let output = run(&mut exponential, &TRACE[10..]);
+
+        assert!(close(output[0].unwrap(), 1.35));
+        assert!(output.iter().flatten().all(|v| (1.34..1.36).contains(v)));
+
+        let output = run(&mut exponential, &[1.62; 8]);
+        assert!(close(output[7].unwrap(), 1.62));
+    }
+
+    #[test]
+    fn kalman_smooths_noise_and_tracks_step() {
+        let mut kalman = Kalman::new(I8F24!(0.001), I8F24!(0.01));
+        let noisy = [1.62, 1.63, 1.61, 1.62, 1.64, 1.62, 1.61, 1.63, 1.62];
+        let output = run(&mut kalman, &noisy);
+
+        assert!(close(output[0].unwrap(), 1.62));
+        let output: Vec<f64> = output.into_iter().flatten().collect();
+        assert!(spread(&output) < spread(&noisy));
+
+        let output = run(&mut kalman, &[1.35; 30]);
+        assert!(output[29].is_some_and(|v| (v - 1.35).abs() < 0.05));
+    }
+
+    #[test]
+    fn stage_reset_restarts_warm_up() {
+        let mut stage = Stage::new(Config::Median { window: 3 });
+        assert!(run(&mut stage, &TRACE[..3])[2].is_some());
+
+        stage.reset();
+        assert_eq!(run(&mut stage, &TRACE[10..11]), [None]);
+
+        let mut stage = Stage::new(Config::Kalman {
+            process_noise: I8F24!(0.001),
+            measurement_noise: I8F24!(0.01),
+        });
+        run(&mut stage, &TRACE[..10]);
+
+        stage.reset();
+        assert!(close(run(&mut stage, &TRACE[10..11])[0].unwrap(), 1.35));
+    }
+}
@@ -0,0 +1,178 @@
+use fixed::types::I8F24;
+use fixed_macro::types::I8F24;
+
+const WINDOW: usize = 8;
+
+#[derive(Clone, Copy, Default, PartialEq, Debug)]
+pub enum Health {
+    #[default]
+    Good,
+    Stuck,
+    Jumping,
+    Noisy,
+}
+
+#[derive(Clone, Copy)]
+pub struct Limits {
+    pub stuck_samples: u16,
+    pub max_jump: I8F24,
+    pub max_spread: I8F24,
+}
+
+pub struct Monitor {
+    limits: Limits,
+    samples: [I8F24; WINDOW],
+    len: usize,
+    next: usize,
+    unchanged: u16,
+    jumped: usize,
+}
+
+#[cfg(feature = "defmt")]
+impl defmt::Format for Health {
+    fn format(&self, fmt: defmt::Formatter) {
+        match self {
+            Self::Good => defmt::write!(fmt, "sensor is healthy"),
+            Self::Stuck => defmt::write!(fmt, "sensor reading is stuck"),
+            Self::Jumping => defmt::write!(fmt, "sensor reading jumps implausibly"),
+            Self::Noisy => defmt::write!(fmt, "sensor is noisy or floating"),
+        }
+    }
+}
+
+impl Limits {
+    pub const DEFAULT: Self = Self {
+        stuck_samples: 600,
+        max_jump: I8F24!(25),
+        max_spread: I8F24!(15),
+    };
+}
+
+impl Monitor {
+    pub fn new(limits: Limits) -> Self {
+        Self {
+            limits,
+            samples: [I8F24::ZERO; WINDOW],
+            len: 0,
+            next: 0,
+            unchanged: 0,
+            jumped: 0,
+        }
+    }
+
+    pub fn reset(&mut self) {
+        self.len = 0;
+        self.next = 0;
+        self.unchanged = 0;
+        self.jumped = 0;
+    }
+
+    pub fn update(&mut self, sample: I8F24) -> Health {
+        if let Some(previous) = self.previous() {
+            let delta = sample.saturating_sub(previous).abs();
+
+            if delta == I8F24::ZERO {
+                self.unchanged = self.unchanged.saturating_add(1);
+            } else {
+                self.unchanged = 0;
+            }
+
+            if delta > self.limits.max_jump {
+                self.jumped = WINDOW;
+            } else {
+                self.jumped = self.jumped.saturating_sub(1);
+            }
+        }
+
+        self.samples[self.next] = sample;
+        self.next = (self.next + 1) % WINDOW;
+        self.len = (self.len + 1).min(WINDOW);
+
+        self.health()
+    }
+
+    pub fn spread(&self) -> I8F24 {
+        let samples = self.samples[..self.len].iter();
+        let min = samples.clone().min().copied().unwrap_or_default();
+        let max = samples.max().copied().unwrap_or_default();
+
+        max.saturating_sub(min)
+    }
+
+    fn previous(&self) -> Option<I8F24> {
+        if self.len == 0 {
+            return None;
+        }
+
+        Some(self.samples[(self.next + WINDOW - 1) % WINDOW])
+    }
+
+    fn health(&self) -> Health {
+        if self.unchanged >= self.limits.stuck_samples {
+            return Health::Stuck;
+        }
+
+        if self.jumped > 0 {
+            return Health::Jumping;
+        }
+
+        if self.len == WINDOW && self.spread() > self.limits.max_spread {
+            return Health::Noisy;
+        }
+
+        Health::Good
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LIMITS: Limits = Limits {
+        stuck_samples: 4,
+        ..Limits::DEFAULT
+    };
+
+    fn run(monitor: &mut Monitor, trace: &[f64]) -> Health {
+        let update = |&percent| monitor.update(I8F24::from_num(percent));
+        trace.iter().map(update).last().unwrap_or_default()
+    }
+
+    #[test]
+    fn tolerates_slow_wet_down() {
+        let mut monitor = Monitor::new(LIMITS);
+        let trace = [41.0, 43.5, 46.0, 48.0, 51.5, 53.0, 55.5, 52.0, 54.0];
+
+        assert_eq!(run(&mut monitor, &trace), Health::Good);
+    }
+
+    #[test]
+    fn flags_stuck_reading() {
+        let mut monitor = Monitor::new(LIMITS);
+
+        assert_eq!(run(&mut monitor, &[62.0; 4]), Health::Good);
+        assert_eq!(run(&mut monitor, &[62.0]), Health::Stuck);
+        assert_eq!(run(&mut monitor, &[62.5]), Health::Good);
+    }
+
+    #[test]
+    fn flags_jump_until_window_passes() {
+        let mut monitor = Monitor::new(LIMITS);
+
+        assert_eq!(run(&mut monitor, &[60.0, 61.0, 95.0]), Health::Jumping);
+        let settled = [95.5, 96.0, 95.5, 96.0, 95.5, 96.0, 95.5];
+        assert_eq!(run(&mut monitor, &settled), Health::Jumping);
+        assert_eq!(run(&mut monitor, &[96.0]), Health::Good);
+    }
+
+    #[test]
+    fn flags_noisy_window() {
+        let mut monitor = Monitor::new(LIMITS);
+        let trace = [50.0, 62.0, 48.0, 64.0, 47.0, 63.0, 49.0, 65.0];
+
+        assert_eq!(run(&mut monitor, &trace), Health::Noisy);
+
+        monitor.reset();
+        assert_eq!(run(&mut monitor, &trace[..4]), Health::Good);
+    }
+}
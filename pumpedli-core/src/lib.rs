@@ -0,0 +1,7 @@
+#![cfg_attr(not(test), no_std)]
+
+pub mod cd4067;
+pub mod current;
+pub mod filter;
+pub mod health;
+pub mod scaling;
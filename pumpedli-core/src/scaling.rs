@@ -0,0 +1,139 @@
+use core::fmt;
+
+use fixed::traits::FromFixed;
+use fixed::types::I8F24;
+use fixed_macro::types::I8F24;
+
+pub struct Scaling {
+    voltage_at_0: I8F24,
+    per_percent: I8F24,
+    no_sensor: I8F24,
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum ValueOutOfRange {
+    Under(i32),
+    Over(i32),
+    None,
+}
+
+impl fmt::Display for ValueOutOfRange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Under(min) => write!(f, "value below minimum of {min}: voltage is too high"),
+            Self::Over(max) => write!(f, "value above maximum of {max}: voltage is too low"),
+            Self::None => write!(f, "no value"),
+        }
+    }
+}
+
+impl Scaling {
+    pub const TYPE0_5V: Self = Self::new(I8F24!(3.3), I8F24!(1.5));
+    pub const TYPE0_3V3: Self = Self::new(I8F24!(2.178), I8F24!(0.99));
+    pub const TYPE0_RATIO: Self = Self::ratiometric(I8F24!(0.66), I8F24!(0.3));
+
+    pub const fn new(voltage_at_0: I8F24, voltage_at_100: I8F24) -> Self {
+        Self::with_cutoff(voltage_at_0, voltage_at_100, I8F24!(0.9))
+    }
+
+    pub const fn ratiometric(ratio_at_0: I8F24, ratio_at_100: I8F24) -> Self {
+        let wet = if ratio_at_100.to_bits() < ratio_at_0.to_bits() {
+            ratio_at_100
+        } else {
+            ratio_at_0
+        };
+
+        Self::with_cutoff(ratio_at_0, ratio_at_100, wet.saturating_mul(I8F24!(0.6)))
+    }
+
+    const fn with_cutoff(voltage_at_0: I8F24, voltage_at_100: I8F24, no_sensor: I8F24) -> Self {
+        Self {
+            voltage_at_0,
+            per_percent: voltage_at_100
+                .saturating_sub(voltage_at_0)
+                .saturating_div(I8F24!(100)),
+            no_sensor,
+        }
+    }
+
+    pub fn convert_voltage(&self, voltage: &I8F24) -> Result<i32, ValueOutOfRange> {
+        const LO_CUTOFF: i32 = -5;
+        const HI_CUTOFF: i32 = 106;
+
+        if *voltage <= self.no_sensor {
+            return Err(ValueOutOfRange::None);
+        }
+
+        let value = i32::from_fixed(self.percent(*voltage));
+
+        match value {
+            i32::MIN..LO_CUTOFF => Err(ValueOutOfRange::Under(LO_CUTOFF)),
+            LO_CUTOFF..HI_CUTOFF => Ok(value.clamp(0, 100)),
+            HI_CUTOFF..=i32::MAX => Err(ValueOutOfRange::Over(HI_CUTOFF)),
+        }
+    }
+
+    pub fn percent(&self, voltage: I8F24) -> I8F24 {
+        voltage
+            .saturating_sub(self.voltage_at_0)
+            .saturating_div(self.per_percent)
+    }
+
+    pub fn convert_value(&self, value: i32) -> I8F24 {
+        let value = I8F24::saturating_from_num(value);
+        self.voltage_at_0
+            .saturating_add(value.saturating_mul(self.per_percent))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn near(result: Result<i32, ValueOutOfRange>, expected: i32) -> bool {
+        result.is_ok_and(|value| (value - expected).abs() <= 1)
+    }
+
+    #[test]
+    fn converts_voltage_between_endpoints() {
+        let scaling = Scaling::TYPE0_5V;
+
+        assert!(near(scaling.convert_voltage(&I8F24!(3.3)), 0));
+        assert!(near(scaling.convert_voltage(&I8F24!(2.4)), 50));
+        assert!(near(scaling.convert_voltage(&I8F24!(1.5)), 100));
+    }
+
+    #[test]
+    fn rejects_voltage_outside_range() {
+        let scaling = Scaling::TYPE0_5V;
+
+        let under = scaling.convert_voltage(&I8F24!(3.6));
+        assert_eq!(under, Err(ValueOutOfRange::Under(-5)));
+
+        let over = scaling.convert_voltage(&I8F24!(1.3));
+        assert_eq!(over, Err(ValueOutOfRange::Over(106)));
+
+        let none = scaling.convert_voltage(&I8F24!(0.5));
+        assert_eq!(none, Err(ValueOutOfRange::None));
+    }
+
+    #[test]
+    fn converts_narrow_ratiometric_span() {
+        let scaling = Scaling::TYPE0_RATIO;
+
+        assert!(near(scaling.convert_voltage(&I8F24!(0.66)), 0));
+        assert!(near(scaling.convert_voltage(&I8F24!(0.48)), 50));
+        assert!(near(scaling.convert_voltage(&I8F24!(0.3)), 100));
+
+        let none = scaling.convert_voltage(&I8F24!(0.17));
+        assert_eq!(none, Err(ValueOutOfRange::None));
+    }
+
+    #[test]
+    fn converts_value_back_to_voltage() {
+        let scaling = Scaling::TYPE0_RATIO;
+        let voltage = scaling.convert_value(60);
+
+        assert!(near(scaling.convert_voltage(&voltage), 60));
+    }
+}
@@ -0,0 +1,311 @@
+use defmt::{debug, warn, Format};
+use embassy_rp::adc::{self, Adc};
+use embassy_rp::gpio::Output;
+use embassy_rp::pwm::{self, Pwm};
+use embassy_time::{Duration, Instant, Timer};
+
+pub use pumpedli_core::current::Calibration;
+
+use crate::lockout::{self, Lockout};
+use crate::program::ProgramFault;
+use crate::{flow, mux, watchdog};
+
+pub struct Sequence {
+    pub valve_lead: Duration,
+    pub soft_start: Duration,
+    pub pressure_bleed: Duration,
+}
+
+pub struct PwmPump<'a> {
+    pwm: Pwm<'a>,
+    config: pwm::Config,
+}
+
+pub struct Protection {
+    pub min_milliamps: i32,
+    pub max_milliamps: i32,
+    pub inrush: Duration,
+    pub trip: Duration,
+    pub no_flow: Duration,
+}
+
+pub struct AdcCurrent<'a> {
+    adc: Adc<'a, adc::Async>,
+    channel: adc::Channel<'a>,
+    calibration: Calibration,
+}
+
+pub struct Sensed<P, S>(pub P, pub S);
+
+pub enum StartError<E> {
+    Valve(E),
+    Locked(lockout::Reason),
+}
+
+pub trait Pump {
+    fn set_duty(&mut self, percent: u8);
+
+    #[allow(async_fn_in_trait)]
+    async fn milliamps(&mut self) -> Option<i32> {
+        None
+    }
+}
+
+#[allow(async_fn_in_trait)]
+pub trait CurrentSensor {
+    async fn milliamps(&mut self) -> Option<i32>;
+}
+
+pub struct Actuator<'a, P, V> {
+    pump: P,
+    valves: V,
+    sequence: Sequence,
+    protection: Option<Protection>,
+    flow: Option<&'a flow::Counter>,
+    lockout: &'a Lockout,
+    runtime: &'a watchdog::Runtime,
+    idle: Instant,
+}
+
+impl Sequence {
+    pub const DEFAULT: Self = Self {
+        valve_lead: Duration::from_millis(250),
+        soft_start: Duration::from_millis(500),
+        pressure_bleed: Duration::from_millis(1000),
+    };
+}
+
+impl<'a> PwmPump<'a> {
+    pub fn new(mut pwm: Pwm<'a>, mut config: pwm::Config) -> Self {
+        config.top = config.top.min(u16::MAX - 1);
+        config.compare_a = 0;
+        config.compare_b = 0;
+        pwm.set_config(&config);
+
+        Self { pwm, config }
+    }
+}
+
+impl Pump for PwmPump<'_> {
+    fn set_duty(&mut self, percent: u8) {
+        let period = u32::from(self.config.top) + 1;
+        let compare = period * u32::from(percent.min(100)) / 100;
+
+        self.config.compare_a = compare as u16;
+        self.config.compare_b = compare as u16;
+        self.pwm.set_config(&self.config);
+    }
+}
+
+impl Pump for Output<'_> {
+    fn set_duty(&mut self, percent: u8) {
+        self.set_level((percent > 0).into());
+    }
+}
+
+impl<P: Pump, S: CurrentSensor> Pump for Sensed<P, S> {
+    fn set_duty(&mut self, percent: u8) {
+        self.0.set_duty(percent);
+    }
+
+    async fn milliamps(&mut self) -> Option<i32> {
+        self.1.milliamps().await
+    }
+}
+
+impl<E: Format> Format for StartError<E> {
+    fn format(&self, fmt: defmt::Formatter) {
+        match self {
+            Self::Valve(e) => defmt::write!(fmt, "valve error: {}", e),
+            Self::Locked(reason) => defmt::write!(fmt, "locked out: {}", reason),
+        }
+    }
+}
+
+impl<'a> AdcCurrent<'a> {
+    pub fn new(
+        adc: Adc<'a, adc::Async>,
+        channel: adc::Channel<'a>,
+        calibration: Calibration,
+    ) -> Self {
+        Self {
+            adc,
+            channel,
+            calibration,
+        }
+    }
+}
+
+impl CurrentSensor for AdcCurrent<'_> {
+    async fn milliamps(&mut self) -> Option<i32> {
+        let count = self.adc.read(&mut self.channel).await.ok()?;
+        Some(self.calibration.milliamps(count))
+    }
+}
+
+impl<'a, P: Pump, V: mux::Valves<Error: Format>> Actuator<'a, P, V> {
+    pub fn new(
+        mut pump: P,
+        valves: V,
+        sequence: Sequence,
+        protection: Option<Protection>,
+        flow: Option<&'a flow::Counter>,
+        lockout: &'a Lockout,
+        runtime: &'a watchdog::Runtime,
+    ) -> Self {
+        pump.set_duty(0);
+        runtime.stop();
+
+        Self {
+            pump,
+            valves,
+            sequence,
+            protection,
+            flow,
+            lockout,
+            runtime,
+            idle: Instant::now(),
+        }
+    }
+
+    pub fn idle_since(&self) -> Instant {
+        self.idle
+    }
+
+    pub async fn start(&mut self, valve: usize, duty: u8) -> Result<(), StartError<V::Error>> {
+        const STEPS: u32 = 20;
+
+        if let Some(reason) = self.lockout.engaged() {
+            return Err(StartError::Locked(reason));
+        }
+
+        self.pump.set_duty(0);
+
+        debug!("opening valve {}...", valve);
+        if let Err(e) = self.valves.open(valve).await {
+            self.close_all().await;
+            self.idle = Instant::now();
+            return Err(StartError::Valve(e));
+        }
+
+        Timer::after(self.sequence.valve_lead).await;
+
+        debug!("starting pump at {}% duty...", duty);
+        self.runtime.start();
+
+        if self.sequence.soft_start > Duration::from_ticks(0) {
+            for step in 1..STEPS {
+                self.pump.set_duty((u32::from(duty) * step / STEPS) as u8);
+                Timer::after(self.sequence.soft_start / STEPS).await;
+            }
+        }
+
+        self.pump.set_duty(duty);
+
+        Ok(())
+    }
+
+    pub async fn stop(&mut self, valve: usize) -> Result<(), V::Error> {
+        debug!("stopping pump...");
+        self.pump.set_duty(0);
+        self.runtime.stop();
+
+        Timer::after(self.sequence.pressure_bleed).await;
+
+        debug!("closing valve {}...", valve);
+        let result = self.valves.close(valve).await;
+        if result.is_err() {
+            self.close_all().await;
+        }
+
+        self.idle = Instant::now();
+        result
+    }
+
+    pub async fn shut_off(&mut self) {
+        self.pump.set_duty(0);
+        self.runtime.stop();
+        self.close_all().await;
+    }
+
+    pub async fn supervise(&mut self, volume: Option<u32>) -> Result<(), ProgramFault> {
+        const SAMPLE: Duration = Duration::from_millis(100);
+
+        let started = Instant::now();
+        let delivered_from = self.flow.map(|flow| flow.milliliters());
+        let mut window = (started, self.flow.map(|flow| flow.pulses()));
+        let mut tripping: Option<(ProgramFault, Instant)> = None;
+
+        loop {
+            Timer::after(SAMPLE).await;
+
+            if let Some(reason) = self.lockout.engaged() {
+                warn!("locked out by {} while running", reason);
+                return Err(self.trip(ProgramFault::LockedOut(reason)));
+            }
+
+            if let (Some(flow), Some(volume), Some(from)) = (self.flow, volume, delivered_from) {
+                if flow.milliliters().wrapping_sub(from) >= volume {
+                    debug!("delivered {} ml", volume);
+                    return Ok(());
+                }
+            }
+
+            let Some(ref protection) = self.protection else {
+                continue;
+            };
+
+            if let (Some(flow), (since, Some(pulses))) = (self.flow, window) {
+                if since.elapsed() >= protection.no_flow {
+                    if flow.pulses() == pulses {
+                        warn!("no flow for {} ms", protection.no_flow.as_millis());
+                        return Err(self.trip(ProgramFault::NoFlow));
+                    }
+
+                    window = (Instant::now(), Some(flow.pulses()));
+                }
+            }
+
+            if started.elapsed() < protection.inrush {
+                continue;
+            }
+
+            let Some(milliamps) = self.pump.milliamps().await else {
+                continue;
+            };
+
+            let fault = if milliamps < protection.min_milliamps {
+                ProgramFault::DryRun
+            } else if milliamps > protection.max_milliamps {
+                ProgramFault::Blockage
+            } else {
+                tripping = None;
+                continue;
+            };
+
+            match tripping {
+                Some((tripped, since)) if tripped == fault => {
+                    if since.elapsed() < protection.trip {
+                        continue;
+                    }
+
+                    warn!("pump current is {} mA: {}", milliamps, fault);
+                    return Err(self.trip(fault));
+                }
+                _ => tripping = Some((fault, Instant::now())),
+            }
+        }
+    }
+
+    fn trip(&mut self, fault: ProgramFault) -> ProgramFault {
+        self.pump.set_duty(0);
+        self.runtime.stop();
+        fault
+    }
+
+    async fn close_all(&mut self) {
+        if let Err(e) = self.valves.close_all().await {
+            warn!("failed to close valves: {}", e);
+        }
+    }
+}
@@ -1,20 +1,107 @@
 use core::ops::RangeInclusive;
+use core::{cmp, fmt};
 
-use defmt::{unwrap, warn};
+use defmt::{debug, warn, Format};
+use embassy_rp::gpio::Output;
+use embassy_time::{Duration, Instant, Timer};
 use embedded_hal_async::i2c::I2c;
 use fixed::types::I8F24;
 use fixed_macro::types::I8F24;
+use heapless::Vec;
 
-use crate::dev::ads1115::{Addr, Ads1115, Channel};
-use crate::reading::{Reading, ReadingPublisher, ReadingResult};
-use crate::{adc, control};
+use crate::dev::ads1115::{Addr, Ads1115, Ads1115Error, Gain, Settings, Source};
+use crate::filter::{self, Filter};
+use crate::health::{self, Health};
+use crate::program::{Program, ProgramState};
+use crate::reading::{Diagnostic, Reading, ReadingPublisher, ReadingResult};
+use crate::{adc, control, watchdog};
 
-pub struct Input(pub Addr, pub Channel);
+pub struct Input(pub Addr, pub Source, pub Settings);
+
+pub struct Excitation<'a>(pub Output<'a>, pub Duration);
+
+#[derive(Clone, Copy)]
+pub struct Reference(pub Source, pub I8F24);
+
+pub struct Sampling {
+    pub period: Option<Duration>,
+    pub window_dwell: Option<Duration>,
+    pub reference: Option<Reference>,
+    pub oversampling: Option<Oversampling>,
+    pub filter: filter::Config,
+    pub health: health::Limits,
+    pub pump_settle: Option<Duration>,
+    pub diagnostics: bool,
+}
+
+pub struct Oversampling {
+    pub ratio: u16,
+    pub samples_per_second: u64,
+}
+
+struct Sample {
+    code: i16,
+    voltage: I8F24,
+    ratio: Option<I8F24>,
+    crossed: bool,
+}
+
+#[derive(Clone, Copy, PartialEq, Format)]
+pub enum Fault {
+    Bus,
+    Timeout,
+    ConfigMismatch,
+    Unsupported,
+    Absent,
+    Reference,
+}
 
 pub struct Converter<'a, T: I2c> {
     ads1115: Ads1115<'a, T>,
     control_loops: [&'a control::Loop<'a>; 4],
+    filters: [filter::Stage; 4],
+    monitors: [health::Monitor; 4],
     publisher: ReadingPublisher<'a>,
+    heartbeat: &'a watchdog::Heartbeat,
+    pump_runtime: &'a watchdog::Runtime,
+    excitation: Option<Excitation<'a>>,
+}
+
+impl fmt::Display for Fault {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Bus => write!(f, "ADC bus error"),
+            Self::Timeout => write!(f, "ADC conversion timed out"),
+            Self::ConfigMismatch => write!(f, "ADC config readback mismatch"),
+            Self::Unsupported => write!(f, "input not supported by ADC"),
+            Self::Absent => write!(f, "ADC not present"),
+            Self::Reference => write!(f, "supply reference out of range"),
+        }
+    }
+}
+
+impl<E> From<Ads1115Error<E>> for Fault {
+    fn from(value: Ads1115Error<E>) -> Self {
+        match value {
+            Ads1115Error::Bus(_) => Self::Bus,
+            Ads1115Error::Timeout => Self::Timeout,
+            Ads1115Error::ConfigMismatch => Self::ConfigMismatch,
+            Ads1115Error::Unsupported => Self::Unsupported,
+        }
+    }
+}
+
+impl Sampling {
+    pub const DEFAULT: Self = Self {
+        period: None,
+        window_dwell: None,
+        reference: None,
+        oversampling: None,
+        filter: filter::Config::DEFAULT,
+        health: health::Limits::DEFAULT,
+        pump_settle: None,
+        diagnostics: false,
+    };
 }
 
 impl<'a, T: I2c> Converter<'a, T> {
@@ -22,62 +109,358 @@ impl<'a, T: I2c> Converter<'a, T> {
         ads1115: Ads1115<'a, T>,
         control_loops: [&'a control::Loop<'a>; 4],
         publisher: ReadingPublisher<'a>,
+        heartbeat: &'a watchdog::Heartbeat,
+        pump_runtime: &'a watchdog::Runtime,
+        excitation: Option<Excitation<'a>>,
     ) -> Self {
         Self {
             ads1115,
             control_loops,
+            filters: control_loops.map(|l| filter::Stage::new(l.sampling.filter)),
+            monitors: control_loops.map(|l| health::Monitor::new(l.sampling.health)),
             publisher,
+            heartbeat,
+            pump_runtime,
+            excitation,
         }
     }
 
     pub async fn run(mut self) -> ! {
-        const SAMPLES: i32 = 5;
-        const NOISE: RangeInclusive<I8F24> = I8F24!(-0.1)..=I8F24!(0.1);
+        const RETRY: Duration = Duration::from_secs(10);
+        const SETTLE_POLL: Duration = Duration::from_millis(100);
 
-        let mut average: [(I8F24, i32); 4] = Default::default();
-        let mut results: [Option<ReadingResult<i32>>; 4] = Default::default();
+        let mut results: [Option<(ReadingResult<i32>, Health)>; 4] = Default::default();
+        let mut due = [Instant::from_ticks(0); 4];
+        let mut counts = [0u32; 4];
+        let mut ready = false;
 
         loop {
-            let control_loops = self.control_loops.into_iter();
+            self.heartbeat.check_in();
 
-            let zip = control_loops
-                .zip(average.iter_mut())
-                .zip(results.iter_mut());
+            if !ready {
+                if let Err(e) = self.ads1115.init().await {
+                    let fault = match self.ads1115.probe().await {
+                        true => Fault::from(e),
+                        false => Fault::Absent,
+                    };
+                    warn!("init error: {}", fault);
 
-            for ((control_loop, (ref mut average, ref mut count)), result) in zip {
-                let control::Loop { adc_input, .. } = *control_loop;
-                let adc::Input(_, channel) = *adc_input;
+                    let zip = self.control_loops.into_iter().zip(results.iter_mut());
+                    for (control_loop, result) in zip {
+                        let new_result = ReadingResult::Fault(fault);
+                        self.publish(control_loop, result, new_result, Health::Good)
+                            .await;
+                    }
 
-                let Ok(voltage) = self.ads1115.read_voltage(channel).await else {
-                    warn!("read error");
+                    Timer::after(RETRY).await;
                     continue;
-                };
+                }
 
-                let delta = voltage - *average;
+                ready = true;
+            }
 
-                *count += 1;
-                *average += unwrap!(delta.checked_div_int(*count));
+            let now = Instant::now();
+            let zip = due.into_iter().zip(self.control_loops);
+            let next = zip
+                .map(|(due, control_loop)| match self.settled(control_loop) {
+                    true => due,
+                    false => cmp::max(due, now + SETTLE_POLL),
+                })
+                .min()
+                .unwrap_or(now);
 
-                let scaling = control_loop.scaling.lock().await;
-                let new_result = scaling.convert_voltage(average);
+            if next > now {
+                Timer::after(cmp::min(next - now, watchdog::CHECK_IN)).await;
+                continue;
+            }
 
-                if *count < SAMPLES {
-                    if *count % 2 == 0 || NOISE.contains(&delta) {
-                        continue;
+            if let Some(Excitation(ref mut rail, warm_up)) = self.excitation {
+                rail.set_high();
+                Timer::after(warm_up).await;
+            }
+
+            let mut supplies = Vec::new();
+
+            for (index, result) in results.iter_mut().enumerate() {
+                let control_loop = self.control_loops[index];
+                if due[index] > now || !self.settled(control_loop) {
+                    continue;
+                }
+
+                let period = control_loop.sampling.period.unwrap_or_default();
+                due[index] = now + period;
+
+                self.heartbeat.check_in();
+
+                let others = due.iter().enumerate().filter(|&(other, _)| other != index);
+                let until = others.map(|(_, &due)| due).min().unwrap_or(Instant::MAX);
+
+                let previous = result.map(|(previous, _)| previous);
+                let sample = self
+                    .sample(control_loop, &previous, until, &mut supplies)
+                    .await;
+                let sample = match sample {
+                    Ok(Some(sample)) => sample,
+                    Ok(None) => continue,
+                    Err(fault) => {
+                        warn!("read error: {}", fault);
+                        self.filters[index].reset();
+                        self.monitors[index].reset();
+                        counts[index] = 0;
+
+                        let new_result = ReadingResult::Fault(fault);
+                        self.publish(control_loop, result, new_result, Health::Good)
+                            .await;
+
+                        if let Fault::Unsupported | Fault::Reference = fault {
+                            continue;
+                        }
+
+                        ready = false;
+                        break;
                     }
+                };
+
+                if !self.settled(control_loop) {
+                    debug!(
+                        "input {}: discarding sample taken while pumping",
+                        control_loop.adc_input.1
+                    );
+                    continue;
+                }
+
+                let Sample {
+                    code,
+                    voltage,
+                    ratio,
+                    crossed,
+                } = sample;
+                let value = ratio.unwrap_or(voltage);
+
+                let health = if self.pump_runtime.idle_for(Instant::now()).is_none() {
+                    self.monitors[index].reset();
+                    result.map(|(_, health)| health).unwrap_or_default()
                 } else {
-                    *count = 1;
-                    *average = voltage;
+                    let percent = control_loop.scaling.lock().await.percent(value);
+                    self.monitors[index].update(percent)
+                };
+
+                if health != Health::Good {
+                    debug!("input {}: {}", control_loop.adc_input.1, health);
                 }
 
-                if result.replace(new_result).is_some_and(|r| r == new_result) {
-                    continue;
+                let filter = &mut self.filters[index];
+                if crossed {
+                    filter.reset();
+                    counts[index] = 0;
                 }
 
-                let reading = Reading::Moisture(control_loop, new_result);
+                let filtered = filter.update(value);
+                let filtered = if crossed { Some(value) } else { filtered };
+                counts[index] = counts[index].saturating_add(1);
+
+                if control_loop.sampling.diagnostics {
+                    let diagnostic = Diagnostic {
+                        code,
+                        voltage,
+                        ratio,
+                        filtered,
+                        samples: counts[index],
+                        spread_percent: self.monitors[index].spread(),
+                    };
 
-                self.publisher.publish(reading).await;
+                    let reading = Reading::Diagnostic(control_loop, diagnostic);
+                    self.publisher.publish(reading).await;
+                }
+
+                let Some(value) = filtered else {
+                    if let Some(previous) = previous {
+                        self.publish(control_loop, result, previous, health).await;
+                    }
+
+                    continue;
+                };
+
+                let scaling = control_loop.scaling.lock().await;
+                let new_result = scaling.convert_voltage(&value).into();
+
+                self.publish(control_loop, result, new_result, health).await;
+            }
+
+            if let Some(Excitation(ref mut rail, _)) = self.excitation {
+                rail.set_low();
+            }
+        }
+    }
+
+    fn settled(&self, control_loop: &control::Loop<'_>) -> bool {
+        let Some(settle) = control_loop.sampling.pump_settle else {
+            return true;
+        };
+
+        self.pump_runtime
+            .idle_for(Instant::now())
+            .is_some_and(|idle| idle >= settle)
+    }
+
+    async fn sample(
+        &mut self,
+        control_loop: &control::Loop<'_>,
+        result: &Option<ReadingResult<i32>>,
+        until: Instant,
+        supplies: &mut Vec<(Source, Gain, I8F24), 4>,
+    ) -> Result<Option<Sample>, Fault> {
+        let control::Loop {
+            adc_input,
+            ref sampling,
+            ..
+        } = *control_loop;
+        let adc::Input(_, source, settings) = *adc_input;
+
+        let supply = match sampling.reference {
+            Some(reference) => Some(self.supply(reference, settings, supplies).await?),
+            None => None,
+        };
+
+        let dwell = sampling.window_dwell.map(|dwell| {
+            let remaining = until.saturating_duration_since(Instant::now());
+            cmp::min(dwell, remaining)
+        });
+
+        let window = match dwell.filter(|&dwell| dwell > Duration::MIN) {
+            Some(dwell) => Self::window(control_loop, result, supply)
+                .await
+                .map(|w| (w, dwell)),
+            None => None,
+        };
+
+        let ((code, voltage), crossed) = if let Some((window, dwell)) = window {
+            let Some(conversion) = self.watch(source, settings, window, dwell).await? else {
+                return Ok(None);
+            };
+
+            debug!("input {} left its window", source);
+            (conversion, true)
+        } else if let Some(ref oversampling) = sampling.oversampling {
+            let settings = Settings {
+                samples_per_second: oversampling.samples_per_second,
+                ..settings
+            };
+            let ratio = oversampling.ratio;
+
+            (
+                self.ads1115.oversample(source, settings, ratio).await?,
+                false,
+            )
+        } else {
+            (self.ads1115.read_voltage(source, settings).await?, false)
+        };
+
+        Ok(Some(Sample {
+            code: code >> (16 - self.ads1115.model().resolution()),
+            voltage,
+            ratio: supply.map(|supply| voltage.saturating_div(supply)),
+            crossed,
+        }))
+    }
+
+    async fn watch(
+        &mut self,
+        source: Source,
+        settings: Settings,
+        window: RangeInclusive<I8F24>,
+        dwell: Duration,
+    ) -> Result<Option<(i16, I8F24)>, Fault> {
+        let deadline = Instant::now() + dwell;
+
+        loop {
+            self.heartbeat.check_in();
+
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            if remaining == Duration::MIN {
+                return Ok(None);
+            }
+
+            let slice = cmp::min(remaining, watchdog::CHECK_IN / 2);
+            let future = self.ads1115.watch(source, settings, window.clone(), slice);
+            if let Some(conversion) = future.await? {
+                return Ok(Some(conversion));
             }
         }
     }
+
+    async fn supply(
+        &mut self,
+        Reference(source, scale): Reference,
+        settings: Settings,
+        supplies: &mut Vec<(Source, Gain, I8F24), 4>,
+    ) -> Result<I8F24, Fault> {
+        const MIN_SUPPLY: I8F24 = I8F24!(0.5);
+
+        let cached = supplies
+            .iter()
+            .find(|&&(s, gain, _)| s == source && gain == settings.gain);
+        if let Some(&(_, _, supply)) = cached {
+            return Ok(supply);
+        }
+
+        let (_, voltage) = self.ads1115.read_voltage(source, settings).await?;
+        let supply = voltage.saturating_mul(scale);
+        if supply < MIN_SUPPLY {
+            return Err(Fault::Reference);
+        }
+
+        supplies.push((source, settings.gain, supply)).ok();
+        Ok(supply)
+    }
+
+    async fn publish(
+        &mut self,
+        control_loop: &'a control::Loop<'a>,
+        result: &mut Option<(ReadingResult<i32>, Health)>,
+        new_result: ReadingResult<i32>,
+        health: Health,
+    ) {
+        if result
+            .replace((new_result, health))
+            .is_some_and(|r| r == (new_result, health))
+        {
+            return;
+        }
+
+        let reading = Reading::Moisture(control_loop, new_result, health);
+
+        self.publisher.publish(reading).await;
+    }
+
+    async fn window(
+        control_loop: &control::Loop<'_>,
+        result: &Option<ReadingResult<i32>>,
+        supply: Option<I8F24>,
+    ) -> Option<RangeInclusive<I8F24>> {
+        let Some(ReadingResult::Ok(value)) = *result else {
+            return None;
+        };
+
+        let program = control_loop.program.lock().await;
+        let Some(Program(ref config, ProgramState::Stopped)) = *program else {
+            return None;
+        };
+
+        let band = config.low_threshold..=config.high_threshold;
+        if !band.contains(&value) {
+            return None;
+        }
+
+        let scaling = control_loop.scaling.lock().await;
+        let mut low = scaling.convert_value(*band.start());
+        let mut high = scaling.convert_value(*band.end());
+
+        if let Some(supply) = supply {
+            low = low.saturating_mul(supply);
+            high = high.saturating_mul(supply);
+        }
+
+        Some(cmp::min(low, high)..=cmp::max(low, high))
+    }
 }
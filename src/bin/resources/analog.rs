@@ -1,21 +1,21 @@
 use pumpedli::adc;
-use pumpedli::dev::ads1115::{Addr, Channel};
+use pumpedli::dev::ads1115::{Addr, Channel, Settings, Source};
 
 pub const INPUTS: [adc::Input; 16] = [
-    adc::Input(Addr::Gnd, Channel::A0),
-    adc::Input(Addr::Gnd, Channel::A1),
-    adc::Input(Addr::Gnd, Channel::A2),
-    adc::Input(Addr::Gnd, Channel::A3),
-    adc::Input(Addr::Vdd, Channel::A0),
-    adc::Input(Addr::Vdd, Channel::A1),
-    adc::Input(Addr::Vdd, Channel::A2),
-    adc::Input(Addr::Vdd, Channel::A3),
-    adc::Input(Addr::Sda, Channel::A0),
-    adc::Input(Addr::Sda, Channel::A1),
-    adc::Input(Addr::Sda, Channel::A2),
-    adc::Input(Addr::Sda, Channel::A3),
-    adc::Input(Addr::Scl, Channel::A0),
-    adc::Input(Addr::Scl, Channel::A1),
-    adc::Input(Addr::Scl, Channel::A2),
-    adc::Input(Addr::Scl, Channel::A3),
+    adc::Input(Addr::Gnd, Source::Single(Channel::A0), Settings::DEFAULT),
+    adc::Input(Addr::Gnd, Source::Single(Channel::A1), Settings::DEFAULT),
+    adc::Input(Addr::Gnd, Source::Single(Channel::A2), Settings::DEFAULT),
+    adc::Input(Addr::Gnd, Source::Single(Channel::A3), Settings::DEFAULT),
+    adc::Input(Addr::Vdd, Source::Single(Channel::A0), Settings::DEFAULT),
+    adc::Input(Addr::Vdd, Source::Single(Channel::A1), Settings::DEFAULT),
+    adc::Input(Addr::Vdd, Source::Single(Channel::A2), Settings::DEFAULT),
+    adc::Input(Addr::Vdd, Source::Single(Channel::A3), Settings::DEFAULT),
+    adc::Input(Addr::Sda, Source::Single(Channel::A0), Settings::DEFAULT),
+    adc::Input(Addr::Sda, Source::Single(Channel::A1), Settings::DEFAULT),
+    adc::Input(Addr::Sda, Source::Single(Channel::A2), Settings::DEFAULT),
+    adc::Input(Addr::Sda, Source::Single(Channel::A3), Settings::DEFAULT),
+    adc::Input(Addr::Scl, Source::Single(Channel::A0), Settings::DEFAULT),
+    adc::Input(Addr::Scl, Source::Single(Channel::A1), Settings::DEFAULT),
+    adc::Input(Addr::Scl, Source::Single(Channel::A2), Settings::DEFAULT),
+    adc::Input(Addr::Scl, Source::Single(Channel::A3), Settings::DEFAULT),
 ];
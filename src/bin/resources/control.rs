@@ -1,6 +1,6 @@
 use embassy_sync::mutex::Mutex;
 use pumpedli::display::lcd199::Position;
-use pumpedli::{control, scaling::Scaling};
+use pumpedli::{adc, control, scaling::Scaling};
 
 use super::{analog, digital};
 
@@ -9,6 +9,7 @@ pub static LOOPS: [control::Loop; 16] = [
         adc_input: &analog::INPUTS[0],
         mux_output: &digital::OUTPUTS[0],
         lcd_position: Some(Position::Top),
+        sampling: adc::Sampling::DEFAULT,
         scaling: Mutex::new(Scaling::TYPE0_3V3),
         program: Mutex::new(None),
     },
@@ -16,6 +17,7 @@ pub static LOOPS: [control::Loop; 16] = [
         adc_input: &analog::INPUTS[1],
         mux_output: &digital::OUTPUTS[1],
         lcd_position: Some(Position::TopLeft),
+        sampling: adc::Sampling::DEFAULT,
         scaling: Mutex::new(Scaling::TYPE0_3V3),
         program: Mutex::new(None),
     },
@@ -23,6 +25,7 @@ pub static LOOPS: [control::Loop; 16] = [
         adc_input: &analog::INPUTS[2],
         mux_output: &digital::OUTPUTS[2],
         lcd_position: Some(Position::TopRight),
+        sampling: adc::Sampling::DEFAULT,
         scaling: Mutex::new(Scaling::TYPE0_3V3),
         program: Mutex::new(None),
     },
@@ -30,6 +33,7 @@ pub static LOOPS: [control::Loop; 16] = [
         adc_input: &analog::INPUTS[3],
         mux_output: &digital::OUTPUTS[3],
         lcd_position: Some(Position::CenterLeft),
+        sampling: adc::Sampling::DEFAULT,
         scaling: Mutex::new(Scaling::TYPE0_3V3),
         program: Mutex::new(None),
     },
@@ -37,6 +41,7 @@ pub static LOOPS: [control::Loop; 16] = [
         adc_input: &analog::INPUTS[4],
         mux_output: &digital::OUTPUTS[4],
         lcd_position: Some(Position::Center),
+        sampling: adc::Sampling::DEFAULT,
         scaling: Mutex::new(Scaling::TYPE0_3V3),
         program: Mutex::new(None),
     },
@@ -44,6 +49,7 @@ pub static LOOPS: [control::Loop; 16] = [
         adc_input: &analog::INPUTS[5],
         mux_output: &digital::OUTPUTS[5],
         lcd_position: Some(Position::CenterRight),
+        sampling: adc::Sampling::DEFAULT,
         scaling: Mutex::new(Scaling::TYPE0_3V3),
         program: Mutex::new(None),
     },
@@ -51,6 +57,7 @@ pub static LOOPS: [control::Loop; 16] = [
         adc_input: &analog::INPUTS[6],
         mux_output: &digital::OUTPUTS[6],
         lcd_position: Some(Position::BottomLeft),
+        sampling: adc::Sampling::DEFAULT,
         scaling: Mutex::new(Scaling::TYPE0_3V3),
         program: Mutex::new(None),
     },
@@ -58,6 +65,7 @@ pub static LOOPS: [control::Loop; 16] = [
         adc_input: &analog::INPUTS[7],
         mux_output: &digital::OUTPUTS[7],
         lcd_position: Some(Position::BottomRight),
+        sampling: adc::Sampling::DEFAULT,
         scaling: Mutex::new(Scaling::TYPE0_3V3),
         program: Mutex::new(None),
     },
@@ -65,6 +73,7 @@ pub static LOOPS: [control::Loop; 16] = [
         adc_input: &analog::INPUTS[8],
         mux_output: &digital::OUTPUTS[8],
         lcd_position: Some(Position::Bottom),
+        sampling: adc::Sampling::DEFAULT,
         scaling: Mutex::new(Scaling::TYPE0_3V3),
         program: Mutex::new(None),
     },
@@ -72,6 +81,7 @@ pub static LOOPS: [control::Loop; 16] = [
         adc_input: &analog::INPUTS[9],
         mux_output: &digital::OUTPUTS[9],
         lcd_position: None,
+        sampling: adc::Sampling::DEFAULT,
         scaling: Mutex::new(Scaling::TYPE0_3V3),
         program: Mutex::new(None),
     },
@@ -79,6 +89,7 @@ pub static LOOPS: [control::Loop; 16] = [
         adc_input: &analog::INPUTS[10],
         mux_output: &digital::OUTPUTS[10],
         lcd_position: None,
+        sampling: adc::Sampling::DEFAULT,
         scaling: Mutex::new(Scaling::TYPE0_3V3),
         program: Mutex::new(None),
     },
@@ -86,6 +97,7 @@ pub static LOOPS: [control::Loop; 16] = [
         adc_input: &analog::INPUTS[11],
         mux_output: &digital::OUTPUTS[11],
         lcd_position: None,
+        sampling: adc::Sampling::DEFAULT,
         scaling: Mutex::new(Scaling::TYPE0_3V3),
         program: Mutex::new(None),
     },
@@ -93,6 +105,7 @@ pub static LOOPS: [control::Loop; 16] = [
         adc_input: &analog::INPUTS[12],
         mux_output: &digital::OUTPUTS[12],
         lcd_position: None,
+        sampling: adc::Sampling::DEFAULT,
         scaling: Mutex::new(Scaling::TYPE0_3V3),
         program: Mutex::new(None),
     },
@@ -100,6 +113,7 @@ pub static LOOPS: [control::Loop; 16] = [
         adc_input: &analog::INPUTS[13],
         mux_output: &digital::OUTPUTS[13],
         lcd_position: None,
+        sampling: adc::Sampling::DEFAULT,
         scaling: Mutex::new(Scaling::TYPE0_3V3),
         program: Mutex::new(None),
     },
@@ -107,6 +121,7 @@ pub static LOOPS: [control::Loop; 16] = [
         adc_input: &analog::INPUTS[14],
         mux_output: &digital::OUTPUTS[14],
         lcd_position: None,
+        sampling: adc::Sampling::DEFAULT,
         scaling: Mutex::new(Scaling::TYPE0_3V3),
         program: Mutex::new(None),
     },
@@ -114,6 +129,7 @@ pub static LOOPS: [control::Loop; 16] = [
         adc_input: &analog::INPUTS[15],
         mux_output: &digital::OUTPUTS[15],
         lcd_position: None,
+        sampling: adc::Sampling::DEFAULT,
         scaling: Mutex::new(Scaling::TYPE0_3V3),
         program: Mutex::new(None),
     },
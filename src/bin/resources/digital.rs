@@ -1,21 +1,20 @@
-use pumpedli::dev::cd4067::Channel;
 use pumpedli::mux;
 
 pub const OUTPUTS: [mux::Output; 16] = [
-    mux::Output(Channel::C0),
-    mux::Output(Channel::C1),
-    mux::Output(Channel::C2),
-    mux::Output(Channel::C3),
-    mux::Output(Channel::C4),
-    mux::Output(Channel::C5),
-    mux::Output(Channel::C6),
-    mux::Output(Channel::C7),
-    mux::Output(Channel::C8),
-    mux::Output(Channel::C9),
-    mux::Output(Channel::C10),
-    mux::Output(Channel::C11),
-    mux::Output(Channel::C12),
-    mux::Output(Channel::C13),
-    mux::Output(Channel::C14),
-    mux::Output(Channel::C15),
+    mux::Output(0),
+    mux::Output(1),
+    mux::Output(2),
+    mux::Output(3),
+    mux::Output(4),
+    mux::Output(5),
+    mux::Output(6),
+    mux::Output(7),
+    mux::Output(8),
+    mux::Output(9),
+    mux::Output(10),
+    mux::Output(11),
+    mux::Output(12),
+    mux::Output(13),
+    mux::Output(14),
+    mux::Output(15),
 ];
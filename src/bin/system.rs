@@ -15,7 +15,8 @@ use embassy_executor::{Executor, Spawner};
 use embassy_rp::bind_interrupts;
 use embassy_rp::gpio::{AnyPin, Input, Level, Output, Pull};
 use embassy_rp::multicore::{spawn_core1, Stack};
-use embassy_rp::peripherals::{I2C1, PIO0, SPI1, USB};
+use embassy_rp::peripherals::{I2C1, PIN_2, PIN_3, PIO0, SPI1, USB};
+use embassy_rp::pwm::{self, Pwm};
 use embassy_rp::{i2c, pio, spi, usb};
 use embassy_sync::blocking_mutex::raw::{CriticalSectionRawMutex, NoopRawMutex};
 use embassy_sync::blocking_mutex::NoopMutex;
@@ -25,26 +26,56 @@ use embassy_sync::signal::Signal;
 use embassy_time::{Delay, Duration};
 use embedded_hal::spi::SpiDevice;
 use embedded_hal_async::i2c::I2c;
+use fixed_macro::types::I8F24;
 use mipidsi::models::GC9A01;
 use mipidsi::options::{ColorInversion, ColorOrder, Orientation, Rotation};
 use mipidsi::Builder;
-use panic_probe as _;
+use pumpedli::actuator::{Actuator, AdcCurrent, Calibration, Protection, PwmPump, Sensed};
 use pumpedli::control::ActionPubSubChannel;
-use pumpedli::dev::ads1115::{Addr, Ads1115};
+use pumpedli::dev::ads1115::{Addr, Ads1115, Model};
 use pumpedli::dev::cd4067::Cd4067;
+use pumpedli::dev::i2c::I2cBus;
 use pumpedli::dev::ws2812::Ws2812;
-use pumpedli::reading::ReadingPubSubChannel;
-use pumpedli::{adc, control, display, led, program, rgb};
+use pumpedli::lockout::Lockout;
+use pumpedli::reading::{ReadingPubSubChannel, SupplyPubSubChannel};
+use pumpedli::{
+    actuator, adc, control, display, flow, leak, led, program, reservoir, rgb, safe_state, watchdog,
+};
 use static_cell::StaticCell;
 
 bind_interrupts!(struct Irqs {
     PIO0_IRQ_0 => pio::InterruptHandler<PIO0>;
     USBCTRL_IRQ => usb::InterruptHandler<USB>;
     I2C1_IRQ => i2c::InterruptHandler<I2C1>;
+    ADC_IRQ_FIFO => embassy_rp::adc::InterruptHandler;
 });
 
-type I2cDriver = i2c::I2c<'static, I2C1, i2c::Async>;
+type I2cDriver = I2cBus<'static, I2C1, PIN_3, PIN_2, Irqs>;
 type SpiDriver = spi::Spi<'static, SPI1, spi::Blocking>;
+type Valves = Cd4067<Output<'static>, MUXES>;
+type Pump = Sensed<PwmPump<'static>, AdcCurrent<'static>>;
+
+const MUXES: usize = 1;
+const PUMP_PWM_TOP: u16 = 6249;
+const PUMP_PROTECTION: Protection = Protection {
+    min_milliamps: 150,
+    max_milliamps: 2500,
+    inrush: Duration::from_secs(1),
+    trip: Duration::from_secs(2),
+    no_flow: Duration::from_secs(5),
+};
+const PUMP_CURRENT: Calibration = Calibration {
+    zero_counts: 2048,
+    milliamps_per_count: I8F24!(2.01),
+};
+const FLOW_PULSES_PER_LITER: u32 = 450;
+const LEAK_DETECTION: leak::Detection = leak::Detection {
+    settle: Duration::from_secs(10),
+    pulses: 20,
+};
+const SENSOR_WARM_UP: Duration = Duration::from_millis(100);
+const RESERVOIR_THRESHOLDS: reservoir::Thresholds = reservoir::Thresholds::new(40, 60, 0, 100);
+const MAX_PUMP_ON: Duration = Duration::from_secs(10 * 60);
 
 #[embassy_executor::task]
 async fn led_task(blinker: led::Blinker<'static>) -> ! {
@@ -65,20 +96,32 @@ async fn logger_task(driver: usb::Driver<'static, USB>) {
 async fn i2c_spawner_task(
     spawner: Spawner,
     i2c_bus: &'static mut Mutex<NoopRawMutex, impl I2c<Error: Format> + 'static>,
-    adc_rdy_pins: [(Addr, AnyPin); 4],
+    adc_pins: [(Addr, Model, AnyPin, Option<AnyPin>); 4],
     control_loops: [&'static control::Loop<'_>; 16],
     reading_bus: &'static ReadingPubSubChannel<'_>,
+    heartbeats: &'static [watchdog::Heartbeat],
+    pump_runtime: &'static watchdog::Runtime,
 ) {
-    let iter = adc_rdy_pins.into_iter();
+    let iter = adc_pins.into_iter().zip(heartbeats);
     let zip = iter.zip(control_loops.chunks(4));
 
-    for ((addr, rdy_pin), control_loops) in zip {
+    for (((addr, model, rdy_pin, excitation_pin), heartbeat), control_loops) in zip {
         let i2c_dev = I2cDevice::new(i2c_bus);
+        let control_loops: [_; 4] = unwrap!(control_loops.try_into());
+
         let rdy = Input::new(rdy_pin, Pull::Up);
-        let ads1115 = unwrap!(Ads1115::new(i2c_dev, addr, rdy).await);
-        let control_loops = unwrap!(control_loops.try_into());
+        let ads1115 = Ads1115::new(i2c_dev, addr, model, Some(rdy));
         let publisher = unwrap!(reading_bus.publisher());
-        let converter = adc::Converter::new(ads1115, control_loops, publisher);
+        let excitation =
+            excitation_pin.map(|pin| adc::Excitation(Output::new(pin, Level::Low), SENSOR_WARM_UP));
+        let converter = adc::Converter::new(
+            ads1115,
+            control_loops,
+            publisher,
+            heartbeat,
+            pump_runtime,
+            excitation,
+        );
         unwrap!(spawner.spawn(adc_task(converter)));
     }
 
@@ -103,30 +146,72 @@ async fn action_spawner_task(
     spawner: Spawner,
     action_bus: &'static ActionPubSubChannel<'_>,
     control_loops: [&'static control::Loop<'_>; 16],
-    control_mutex: &'static Mutex<NoopRawMutex, (Output<'_>, Cd4067<'_>)>,
+    control_mutex: &'static Mutex<NoopRawMutex, Actuator<'static, Pump, Valves>>,
     led: &'static Signal<CriticalSectionRawMutex, led::Mode>,
     rgb: &'static Signal<CriticalSectionRawMutex, rgb::Mode>,
+    heartbeats: &'static [watchdog::Heartbeat],
 ) {
-    for &control::Loop { mux_output, .. } in control_loops {
+    let zip = control_loops.into_iter().zip(heartbeats);
+
+    for (&control::Loop { mux_output, .. }, heartbeat) in zip {
         let subscriber = unwrap!(action_bus.subscriber());
-        let irrigator = control::Irrigator::new(subscriber, mux_output, control_mutex, led, rgb);
+        let irrigator =
+            control::Irrigator::new(subscriber, mux_output, control_mutex, led, rgb, heartbeat);
         unwrap!(spawner.spawn(control_task(irrigator)));
     }
 }
 
 #[embassy_executor::task(pool_size = 16)]
-async fn control_task(mut irrigator: control::Irrigator<'static>) -> ! {
+async fn control_task(mut irrigator: control::Irrigator<'static, Pump, Valves>) -> ! {
     irrigator.run().await
 }
 
+#[embassy_executor::task]
+async fn flow_task(meter: flow::Meter<'static>) -> ! {
+    meter.run().await
+}
+
+#[embassy_executor::task]
+async fn reservoir_task(monitor: reservoir::Monitor<'static>) -> ! {
+    monitor.run().await
+}
+
+#[embassy_executor::task]
+async fn leak_task(monitor: leak::Monitor<'static, Pump, Valves>) -> ! {
+    monitor.run().await
+}
+
+#[embassy_executor::task]
+async fn watchdog_task(supervisor: watchdog::Supervisor<'static>) -> ! {
+    supervisor.run().await
+}
+
 #[embassy_executor::task]
 async fn program_task(mut regulator: program::Regulator<'static>) -> ! {
     regulator.run().await
 }
 
+#[panic_handler]
+fn panic(info: &core::panic::PanicInfo) -> ! {
+    safe_state::enter();
+    defmt::error!("{}", defmt::Display2Format(info));
+    cortex_m::asm::udf()
+}
+
+#[cortex_m_rt::exception]
+unsafe fn HardFault(_frame: &cortex_m_rt::ExceptionFrame) -> ! {
+    safe_state::enter();
+    cortex_m::peripheral::SCB::sys_reset()
+}
+
 #[cortex_m_rt::entry]
 fn main() -> ! {
     let p = embassy_rp::init(Default::default());
+    safe_state::register(&p.PIN_15, Level::Low);
+    let mut pump_config = pwm::Config::default();
+    pump_config.top = PUMP_PWM_TOP;
+    let pump_pwm = Pwm::new_output_b(p.PWM_SLICE7, p.PIN_15, pump_config.clone());
+    let en = safe_state::output(AnyPin::from(p.PIN_16), Level::High);
     let driver = usb::Driver::new(p.USB, Irqs);
 
     static LED_SIGNAL: Signal<CriticalSectionRawMutex, led::Mode> = Signal::new();
@@ -134,18 +219,20 @@ fn main() -> ! {
     let blinker = led::Blinker::new(&LED_SIGNAL, led);
 
     static LED_RGB_SIGNAL: Signal<CriticalSectionRawMutex, rgb::Mode> = Signal::new();
+    static LED_ALARM_SIGNAL: Signal<CriticalSectionRawMutex, Option<rgb::Mode>> = Signal::new();
     let mut pio = pio::Pio::new(p.PIO0, Irqs);
     let ws2812 = Ws2812::new(&mut pio.common, pio.sm0, p.DMA_CH0, p.PIN_23);
-    let control = rgb::Control::new(&LED_RGB_SIGNAL, ws2812);
+    let control = rgb::Control::new(&LED_RGB_SIGNAL, &LED_ALARM_SIGNAL, ws2812);
 
     static I2C_BUS: StaticCell<Mutex<NoopRawMutex, I2cDriver>> = StaticCell::new();
-    let i2c = i2c::I2c::new_async(p.I2C1, p.PIN_3, p.PIN_2, Irqs, i2c::Config::default());
+    let i2c = I2cBus::new(p.I2C1, p.PIN_3, p.PIN_2, Irqs, i2c::Config::default());
     let i2c_bus = I2C_BUS.init(Mutex::new(i2c));
-    let adc_rdy_pins = [
-        (Addr::Gnd, AnyPin::from(p.PIN_4)),
-        (Addr::Vdd, AnyPin::from(p.PIN_5)),
-        (Addr::Sda, AnyPin::from(p.PIN_6)),
-        (Addr::Scl, AnyPin::from(p.PIN_7)),
+    let excitation = Some(AnyPin::from(p.PIN_28));
+    let adc_pins = [
+        (Addr::Gnd, Model::Ads1115, AnyPin::from(p.PIN_4), excitation),
+        (Addr::Vdd, Model::Ads1115, AnyPin::from(p.PIN_5), None),
+        (Addr::Sda, Model::Ads1115, AnyPin::from(p.PIN_6), None),
+        (Addr::Scl, Model::Ads1115, AnyPin::from(p.PIN_7), None),
     ];
 
     let control_loops = resources::control::LOOPS.each_ref();
@@ -159,21 +246,85 @@ fn main() -> ! {
     let _blk = Output::new(AnyPin::from(p.PIN_14), Level::High);
 
     static ACTION_BUS: control::ActionPubSubChannel = PubSubChannel::new();
-    let motor = Output::new(AnyPin::from(p.PIN_15), Level::High);
-    let en = Output::new(AnyPin::from(p.PIN_16), Level::High);
     let s0 = Output::new(AnyPin::from(p.PIN_17), Level::Low);
     let s1 = Output::new(AnyPin::from(p.PIN_18), Level::Low);
     let s2 = Output::new(AnyPin::from(p.PIN_19), Level::Low);
     let s3 = Output::new(AnyPin::from(p.PIN_20), Level::Low);
-    let cd4067 = Cd4067::new(en, s0, s1, s2, s3);
+    let cd4067 = Cd4067::new([en], s0, s1, s2, s3);
+
+    static PUMP_RUNTIME: watchdog::Runtime = watchdog::Runtime::new();
+    let sequence = actuator::Sequence::DEFAULT;
+    let pump_adc = embassy_rp::adc::Adc::new(p.ADC, Irqs, Default::default());
+    let pump_channel = embassy_rp::adc::Channel::new_pin(p.PIN_26, Pull::None);
+    let current = AdcCurrent::new(pump_adc, pump_channel, PUMP_CURRENT);
+    let pump = Sensed(PwmPump::new(pump_pwm, pump_config), current);
+    let protection = Some(PUMP_PROTECTION);
+    static FLOW_COUNTER: flow::Counter = flow::Counter::new(FLOW_PULSES_PER_LITER);
+    let flow = Some(&FLOW_COUNTER);
+    static LOCKOUT: Lockout = Lockout::new();
+    let actuator = Actuator::new(
+        pump,
+        cd4067,
+        sequence,
+        protection,
+        flow,
+        &LOCKOUT,
+        &PUMP_RUNTIME,
+    );
 
-    static CONTROL_MUTEX: StaticCell<Mutex<NoopRawMutex, (Output, Cd4067)>> = StaticCell::new();
-    let control_mutex = CONTROL_MUTEX.init(Mutex::new((motor, cd4067)));
+    static CONTROL_MUTEX: StaticCell<Mutex<NoopRawMutex, Actuator<Pump, Valves>>> =
+        StaticCell::new();
+    let control_mutex = CONTROL_MUTEX.init(Mutex::new(actuator));
 
     static READING_BUS: ReadingPubSubChannel = PubSubChannel::new();
+    static SUPPLY_BUS: SupplyPubSubChannel = PubSubChannel::new();
+    let flow_input = Input::new(p.PIN_21, Pull::Up);
+    let publisher = unwrap!(SUPPLY_BUS.publisher());
+    let meter = flow::Meter::new(flow_input, &FLOW_COUNTER, publisher);
+
+    let float = Input::new(p.PIN_22, Pull::Up);
+    let source = reservoir::Source::Float(float, Level::Low);
+    let refill = safe_state::output(p.PIN_27, Level::Low);
+    let publisher = unwrap!(SUPPLY_BUS.publisher());
+    let reservoir = reservoir::Monitor::new(
+        source,
+        RESERVOIR_THRESHOLDS,
+        Some(refill),
+        &LOCKOUT,
+        publisher,
+    );
+
     let subscriber = unwrap!(READING_BUS.subscriber());
+    let supply = unwrap!(SUPPLY_BUS.subscriber());
     let publisher = unwrap!(ACTION_BUS.publisher());
-    let regulator = program::Regulator::new(subscriber, publisher, &LED_SIGNAL, &LED_RGB_SIGNAL);
+    static HEARTBEATS: [watchdog::Heartbeat; 22] =
+        [const { watchdog::Heartbeat::new(Duration::from_secs(30)) }; 22];
+    let (adc_heartbeats, heartbeats) = HEARTBEATS.split_at(4);
+    let (regulator_heartbeat, heartbeats) = unwrap!(heartbeats.split_first());
+    let (leak_heartbeat, irrigator_heartbeats) = unwrap!(heartbeats.split_first());
+
+    let regulator = program::Regulator::new(
+        subscriber,
+        supply,
+        publisher,
+        &LED_SIGNAL,
+        &LED_RGB_SIGNAL,
+        regulator_heartbeat,
+    );
+
+    let acknowledge = Input::new(p.PIN_0, Pull::Up);
+    let monitor = leak::Monitor::new(
+        control_mutex,
+        &FLOW_COUNTER,
+        LEAK_DETECTION,
+        &LOCKOUT,
+        acknowledge,
+        &LED_ALARM_SIGNAL,
+        leak_heartbeat,
+    );
+
+    let watchdog = embassy_rp::watchdog::Watchdog::new(p.WATCHDOG);
+    let supervisor = watchdog::Supervisor::new(watchdog, &HEARTBEATS, &PUMP_RUNTIME, MAX_PUMP_ON);
 
     static mut CORE1_STACK: Stack<8192> = Stack::new();
     let subscriber = unwrap!(READING_BUS.subscriber());
@@ -227,9 +378,11 @@ fn main() -> ! {
         unwrap!(spawner.spawn(i2c_spawner_task(
             spawner,
             i2c_bus,
-            adc_rdy_pins,
+            adc_pins,
             control_loops,
             &READING_BUS,
+            adc_heartbeats,
+            &PUMP_RUNTIME,
         )));
 
         unwrap!(spawner.spawn(action_spawner_task(
@@ -239,8 +392,13 @@ fn main() -> ! {
             control_mutex,
             &LED_SIGNAL,
             &LED_RGB_SIGNAL,
+            irrigator_heartbeats,
         )));
 
+        unwrap!(spawner.spawn(flow_task(meter)));
+        unwrap!(spawner.spawn(leak_task(monitor)));
+        unwrap!(spawner.spawn(reservoir_task(reservoir)));
+        unwrap!(spawner.spawn(watchdog_task(supervisor)));
         unwrap!(spawner.spawn(program_task(regulator)))
     })
 }
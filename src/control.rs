@@ -1,7 +1,7 @@
 use core::cmp;
 
-use defmt::{debug, trace, warn};
-use embassy_rp::gpio::Output;
+use defmt::{debug, trace, warn, Format};
+use embassy_futures::select::{select, Either};
 use embassy_sync::blocking_mutex::raw::{CriticalSectionRawMutex, NoopRawMutex};
 use embassy_sync::mutex::Mutex;
 use embassy_sync::pubsub::{PubSubChannel, Publisher, Subscriber};
@@ -9,12 +9,12 @@ use embassy_sync::signal::Signal;
 use embassy_time::{with_timeout, Duration, TimeoutError};
 use palette::{named, GetHue, Srgb};
 
-use crate::dev::cd4067::Cd4067;
+use crate::actuator::{Actuator, Pump, StartError};
 use crate::display::lcd199::Position;
 use crate::program::{Program, ProgramConfig, ProgramFault, ProgramState};
 use crate::reading::Reading;
 use crate::scaling::Scaling;
-use crate::{adc, led, mux, rgb};
+use crate::{adc, led, mux, rgb, watchdog};
 
 #[derive(Clone)]
 #[non_exhaustive]
@@ -27,29 +27,32 @@ pub type ActionPublisher<'a> = Publisher<'a, CriticalSectionRawMutex, Action<'a>
 pub type ActionSubscriber<'a> = Subscriber<'a, CriticalSectionRawMutex, Action<'a>, 16, 16, 1>;
 pub type ActionPubSubChannel<'a> = PubSubChannel<CriticalSectionRawMutex, Action<'a>, 16, 16, 1>;
 
-pub struct Irrigator<'a> {
+pub struct Irrigator<'a, P, V> {
     subscriber: ActionSubscriber<'a>,
     mux_output: &'a mux::Output,
-    control_mutex: &'a Mutex<NoopRawMutex, (Output<'a>, Cd4067<'a>)>,
+    control_mutex: &'a Mutex<NoopRawMutex, Actuator<'a, P, V>>,
     led: &'a Signal<CriticalSectionRawMutex, led::Mode>,
     rgb: &'a Signal<CriticalSectionRawMutex, rgb::Mode>,
+    heartbeat: &'a watchdog::Heartbeat,
 }
 
 pub struct Loop<'a> {
     pub adc_input: &'a adc::Input,
     pub mux_output: &'a mux::Output,
     pub lcd_position: Option<Position>,
+    pub sampling: adc::Sampling,
     pub scaling: Mutex<CriticalSectionRawMutex, Scaling>,
     pub program: Mutex<CriticalSectionRawMutex, Option<Program>>,
 }
 
-impl<'a> Irrigator<'a> {
+impl<'a, P: Pump, V: mux::Valves<Error: Format>> Irrigator<'a, P, V> {
     pub fn new(
         subscriber: ActionSubscriber<'a>,
         mux_output: &'a mux::Output,
-        control_mutex: &'a Mutex<NoopRawMutex, (Output<'a>, Cd4067<'a>)>,
+        control_mutex: &'a Mutex<NoopRawMutex, Actuator<'a, P, V>>,
         led: &'a Signal<CriticalSectionRawMutex, led::Mode>,
         rgb: &'a Signal<CriticalSectionRawMutex, rgb::Mode>,
+        heartbeat: &'a watchdog::Heartbeat,
     ) -> Self {
         Self {
             subscriber,
@@ -57,6 +60,7 @@ impl<'a> Irrigator<'a> {
             control_mutex,
             led,
             rgb,
+            heartbeat,
         }
     }
 
@@ -64,12 +68,12 @@ impl<'a> Irrigator<'a> {
         'running: loop {
             use led::Mode::OnOff;
 
-            let action = self.subscriber.next_message_pure().await;
+            let action = self.next_action().await;
             let Action::RunWater(reading) = action else {
                 continue;
             };
 
-            let Reading::Moisture(control_loop, result) = reading else {
+            let Reading::Moisture(control_loop, result, _) = reading else {
                 continue;
             };
 
@@ -85,11 +89,35 @@ impl<'a> Irrigator<'a> {
             let closure = |other| result.partial_cmp(&other);
 
             loop {
-                let Ok(duration) = control_loop.map_config(|c| c.run_duration).await else {
+                let future =
+                    control_loop.map_config(|c| (c.run_duration, c.pump_duty, c.run_volume));
+                let Ok((duration, duty, volume)) = future.await else {
                     break;
                 };
 
-                let Err(TimeoutError) = self.run_water(duration).await else {
+                let result = match self.run_water(duration, duty, volume).await {
+                    Ok(result) => result,
+                    Err(ProgramFault::LockedOut(reason)) => {
+                        debug!("backing off while locked out by {}", reason);
+                        let future = control_loop.map_config(|c| c.pause_duration);
+                        if let Ok(duration) = future.await {
+                            self.pause(duration).await.ok();
+                        }
+
+                        break;
+                    }
+                    Err(fault) => {
+                        let new_state = ProgramState::Faulted { fault };
+                        let future = control_loop.map_state_mut(|state| *state = new_state);
+                        let Ok(_) = future.await else {
+                            break;
+                        };
+
+                        continue 'running;
+                    }
+                };
+
+                let Err(TimeoutError) = result else {
                     break;
                 };
 
@@ -141,19 +169,42 @@ impl<'a> Irrigator<'a> {
         }
     }
 
-    async fn run_water(&mut self, duration: Duration) -> Result<(), TimeoutError> {
+    async fn run_water(
+        &mut self,
+        duration: Duration,
+        duty: u8,
+        volume: Option<u32>,
+    ) -> Result<Result<(), TimeoutError>, ProgramFault> {
         use led::Mode::{Off, On};
         use rgb::Mode::{Color, Off as Black};
 
-        let mux::Output(channel) = *self.mux_output;
+        let mux::Output(valve) = *self.mux_output;
 
-        trace!("waiting to gain control over channel {}...", channel);
-        let mut control_mutex = self.control_mutex.lock().await;
-        let (ref mut motor, ref mut cd4067) = *control_mutex;
+        trace!("waiting to gain control over valve {}...", valve);
+        let mut actuator = loop {
+            self.heartbeat.check_in();
+            let future = self.control_mutex.lock();
+            if let Ok(actuator) = with_timeout(watchdog::CHECK_IN, future).await {
+                break actuator;
+            }
+        };
+
+        debug!("running water on valve {}...", valve);
+        match actuator.start(valve, duty).await {
+            Ok(()) => {}
+            Err(StartError::Locked(reason)) => {
+                warn!(
+                    "not running water on valve {}: locked out by {}",
+                    valve, reason
+                );
+                return Err(ProgramFault::LockedOut(reason));
+            }
+            Err(e) => {
+                warn!("failed to start water on valve {}: {}", valve, e);
+                return Err(ProgramFault::ValveFailure);
+            }
+        }
 
-        debug!("running water on channel {}...", channel);
-        motor.set_high();
-        cd4067.enable(channel);
         self.led.signal(On);
         self.rgb.signal(Color {
             hue: Srgb::<f32>::from(named::BLUE).get_hue(),
@@ -161,15 +212,25 @@ impl<'a> Irrigator<'a> {
         });
 
         debug!("waiting for stop signal...");
-        let result = with_timeout(duration, self.wait_for_stop()).await;
+        let future = select(self.wait_for_stop(), actuator.supervise(volume));
+        let result = with_timeout(duration, future).await;
 
         debug!("water is no longer running");
-        motor.set_low();
-        cd4067.disable();
+        let stopped = actuator.stop(valve).await;
         self.led.signal(Off);
         self.rgb.signal(Black);
 
-        result
+        if let Err(e) = stopped {
+            warn!("failed to stop water on valve {}: {}", valve, e);
+            return Err(ProgramFault::ValveFailure);
+        }
+
+        match result {
+            Ok(Either::First(())) => Ok(Ok(())),
+            Ok(Either::Second(Ok(()))) => Ok(Err(TimeoutError)),
+            Ok(Either::Second(Err(fault))) => Err(fault),
+            Err(TimeoutError) => Ok(Err(TimeoutError)),
+        }
     }
 
     async fn pause(&mut self, duration: Duration) -> Result<(), TimeoutError> {
@@ -190,9 +251,19 @@ impl<'a> Irrigator<'a> {
         result
     }
 
+    async fn next_action(&mut self) -> Action<'a> {
+        loop {
+            self.heartbeat.check_in();
+            let future = self.subscriber.next_message_pure();
+            if let Ok(action) = with_timeout(watchdog::CHECK_IN, future).await {
+                return action;
+            }
+        }
+    }
+
     async fn wait_for_stop(&mut self) {
         loop {
-            let action = self.subscriber.next_message_pure().await;
+            let action = self.next_action().await;
             if let Action::Stop = action {
                 trace!("received command to stop running water");
                 return;
@@ -1,7 +1,10 @@
 mod regs;
 mod vals;
 
-use defmt::{trace, warn};
+use core::ops::RangeInclusive;
+
+use bilge::prelude::*;
+use defmt::{debug, trace};
 use embassy_rp::gpio::Input;
 use embassy_time::{with_timeout, Duration, Timer};
 use embedded_hal_async::i2c::I2c;
@@ -9,56 +12,383 @@ use fixed::types::I8F24;
 use regs::*;
 use vals::*;
 
-pub use vals::{Addr, Channel};
+pub use regs::OpAmpGain;
+pub use vals::{Addr, Ads1115Error, Channel, Gain, Model, Pair, Settings, Source};
 
 pub struct Ads1115<'a, T: I2c> {
     i2c: T,
     addr: Addr,
+    model: Model,
+    config: Config,
+    gains: [OpAmpGain; 8],
+    mux: Option<usize>,
+    rdy: Option<Input<'a>>,
+}
+
+pub struct Conversions<'s, 'a, T: I2c> {
+    ads1115: &'s mut Ads1115<'a, T>,
     config: Config,
-    rdy: Input<'a>,
+    unit: I8F24,
+    period: Duration,
+    discard: u8,
 }
 
 impl<'a, T: I2c> Ads1115<'a, T> {
-    pub async fn new(mut i2c: T, addr: Addr, rdy: Input<'a>) -> Result<Self, T::Error> {
+    pub fn new(i2c: T, addr: Addr, model: Model, rdy: Option<Input<'a>>) -> Self {
+        Self {
+            i2c,
+            addr,
+            model,
+            config: Config::from(Config::DEFAULT),
+            gains: [Self::default_gain(model); 8],
+            mux: None,
+            rdy,
+        }
+    }
+
+    pub async fn probe(&mut self) -> bool {
+        Self::read_config(&mut self.i2c, self.addr).await.is_ok()
+    }
+
+    pub async fn init(&mut self) -> Result<(), Ads1115Error<T::Error>> {
         Timer::after_micros(55).await;
-        let mut config = Self::read_config(&mut i2c, addr).await?;
-        config.set_op_amp_gain(OpAmpGain::Upto4V096);
-        config.set_data_rate(DataRate::Sps64);
+        let mut config = Self::read_config(&mut self.i2c, self.addr).await?;
+        config.set_data_rate(self.model.data_rate(64));
+        config.set_op_mode(OperateMode::SingleShot);
 
-        let mut comparator = config.comparator();
-        comparator.set_comp_queue(CompareQueue::CompareOne);
-        config.set_comparator(comparator);
-        Self::write_thresh(&mut i2c, addr, (0, -1)).await?;
+        if self.model.has_comparator() {
+            let mut comparator = config.comparator();
+            comparator.set_comp_queue(CompareQueue::CompareOne);
+            comparator.set_output_lat(false);
+            comparator.set_comp_mode(CompareMode::AboveThreshold);
+            config.set_comparator(comparator);
+            Self::write_thresh(&mut self.i2c, self.addr, (0, -1)).await?;
+        }
 
-        Ok(Self {
-            i2c,
-            addr,
+        Self::write_config(&mut self.i2c, self.addr, config.clone()).await?;
+
+        if !Self::matches(&Self::read_config(&mut self.i2c, self.addr).await?, &config) {
+            return Err(Ads1115Error::ConfigMismatch);
+        }
+
+        self.config = config;
+        self.gains = [Self::default_gain(self.model); 8];
+        self.mux = None;
+
+        Ok(())
+    }
+
+    pub fn model(&self) -> Model {
+        self.model
+    }
+
+    pub async fn read_voltage(
+        &mut self,
+        source: Source,
+        settings: Settings,
+    ) -> Result<(i16, I8F24), Ads1115Error<T::Error>> {
+        loop {
+            let gain = self.gain(source, settings.gain);
+            let unit = I8F24::from(gain) >> 15;
+            let result = self.convert(source, settings).await?;
+            let voltage: I8F24 = i32::from(result) * unit;
+
+            let saturated = self.saturated(result);
+            if settings.gain == Gain::Auto && self.autorange(source, gain, saturated, voltage) {
+                continue;
+            }
+
+            return Ok((result, voltage));
+        }
+    }
+
+    pub async fn oversample(
+        &mut self,
+        source: Source,
+        settings: Settings,
+        ratio: u16,
+    ) -> Result<(i16, I8F24), Ads1115Error<T::Error>> {
+        let ratio = ratio.max(1);
+
+        loop {
+            let gain = self.gain(source, settings.gain);
+            let mut conversions = self.continuous(source, settings).await?;
+            let unit = conversions.unit;
+
+            let result = conversions.sum(ratio).await;
+            let stopped = conversions.stop().await;
+
+            let (sum, saturated) = result?;
+            stopped?;
+
+            let bits = sum * i64::from(unit.to_bits()) / i64::from(ratio);
+            let voltage = I8F24::from_bits(bits as i32);
+
+            if settings.gain == Gain::Auto && self.autorange(source, gain, saturated, voltage) {
+                continue;
+            }
+
+            let code = (sum / i64::from(ratio)) as i16;
+            return Ok((code, voltage));
+        }
+    }
+
+    pub async fn read_channel_raw(
+        &mut self,
+        source: Source,
+        settings: Settings,
+    ) -> Result<i16, Ads1115Error<T::Error>> {
+        let result = self.convert(source, settings).await?;
+        Ok(result >> (16 - self.model.resolution()))
+    }
+
+    pub async fn continuous(
+        &mut self,
+        source: Source,
+        settings: Settings,
+    ) -> Result<Conversions<'_, 'a, T>, Ads1115Error<T::Error>> {
+        let mut config = self.configure(source, settings)?;
+        let period = self.period(&config);
+        let unit = I8F24::from(config.op_amp_gain()) >> 15;
+        config.set_op_mode(OperateMode::Continuous);
+        Self::write_config(&mut self.i2c, self.addr, config.clone()).await?;
+        self.mux = Some(index(source));
+
+        Ok(Conversions {
+            ads1115: self,
             config,
-            rdy,
+            unit,
+            period,
+            discard: settings.discard,
         })
     }
 
-    pub async fn read_voltage(&mut self, channel: Channel) -> Result<I8F24, T::Error> {
-        let gain = self.config.op_amp_gain();
-        let unit = I8F24::from(gain) >> 15;
-        let result = self.read_channel_raw(channel).await?;
-        Ok(i32::from(result) * unit)
+    pub async fn watch(
+        &mut self,
+        source: Source,
+        settings: Settings,
+        window: RangeInclusive<I8F24>,
+        timeout: Duration,
+    ) -> Result<Option<(i16, I8F24)>, Ads1115Error<T::Error>> {
+        if self.rdy.is_none() || !self.model.has_comparator() {
+            return Err(Ads1115Error::Unsupported);
+        }
+
+        let base = self.configure(source, settings)?;
+        let mut config = base.clone();
+        let full_scale = I8F24::from(config.op_amp_gain());
+        let unit = full_scale >> 15;
+        let code = |voltage: &I8F24| {
+            let code = (i64::from(voltage.to_bits()) << 15) / i64::from(full_scale.to_bits());
+            code.clamp(i16::MIN.into(), i16::MAX.into()) as i16
+        };
+
+        let mut comparator = config.comparator();
+        comparator.set_comp_queue(CompareQueue::CompareTwo);
+        comparator.set_output_lat(true);
+        comparator.set_comp_mode(CompareMode::OutsideWindow);
+        config.set_comparator(comparator);
+        config.set_op_mode(OperateMode::Continuous);
+
+        let thresh = (code(window.start()), code(window.end()));
+        self.mux = Some(index(source));
+
+        let result = self
+            .watch_window(config, thresh, &window, unit, timeout)
+            .await;
+        let restored = self.restore(base).await;
+
+        let conversion = result?;
+        restored?;
+        Ok(conversion)
     }
 
-    pub async fn read_channel_raw(&mut self, channel: Channel) -> Result<i16, T::Error> {
-        let mut config = self.config.clone();
-        let hz = config.data_rate().into();
-        config.set_input_mux(channel.into());
+    async fn watch_window(
+        &mut self,
+        config: Config,
+        thresh: (i16, i16),
+        window: &RangeInclusive<I8F24>,
+        unit: I8F24,
+        timeout: Duration,
+    ) -> Result<Option<(i16, I8F24)>, Ads1115Error<T::Error>> {
+        Self::write_thresh(&mut self.i2c, self.addr, thresh).await?;
         Self::write_config(&mut self.i2c, self.addr, config).await?;
 
-        trace!("waiting for low on ALERT/RDY pin...");
+        let Some(ref mut rdy) = self.rdy else {
+            return Err(Ads1115Error::Unsupported);
+        };
+
+        let future = async {
+            loop {
+                trace!("waiting for window alert on ALERT/RDY pin...");
+                rdy.wait_for_low().await;
 
-        let future = self.rdy.wait_for_low();
-        if with_timeout(Duration::from_hz(hz), future).await.is_err() {
-            warn!("ADC lagged");
+                let result = Self::read_result(&mut self.i2c, self.addr).await?;
+                let voltage = i32::from(result) * unit;
+                if !window.contains(&voltage) {
+                    return Ok((result, voltage));
+                }
+            }
+        };
+
+        let result: Result<_, T::Error> = with_timeout(timeout, future).await.ok().transpose();
+        Ok(result?)
+    }
+
+    async fn restore(&mut self, mut config: Config) -> Result<(), Ads1115Error<T::Error>> {
+        trace!("restoring single-shot conversions");
+        config.set_op_status(false);
+        Self::write_config(&mut self.i2c, self.addr, config).await?;
+
+        if self.model.has_comparator() {
+            Self::write_thresh(&mut self.i2c, self.addr, (0, -1)).await?;
         }
 
-        Self::read_result(&mut self.i2c, self.addr).await
+        // A latched comparator holds ALERT/RDY low until the result is read.
+        Self::read_result(&mut self.i2c, self.addr).await?;
+        Ok(())
+    }
+
+    async fn convert(
+        &mut self,
+        source: Source,
+        settings: Settings,
+    ) -> Result<i16, Ads1115Error<T::Error>> {
+        let config = self.configure(source, settings)?;
+
+        let discard = match self.mux.replace(index(source)) {
+            Some(mux) if mux == index(source) => 0,
+            _ => settings.discard,
+        };
+
+        for _ in 0..discard {
+            trace!("discarding conversion after switching to input {}", source);
+            self.single_shot(config.clone()).await?;
+        }
+
+        self.single_shot(config).await
+    }
+
+    async fn single_shot(&mut self, config: Config) -> Result<i16, Ads1115Error<T::Error>> {
+        let period = self.period(&config);
+        Self::write_config(&mut self.i2c, self.addr, config.clone()).await?;
+
+        let future = async {
+            match self.rdy {
+                Some(ref mut rdy) => {
+                    trace!("waiting for low on ALERT/RDY pin...");
+                    rdy.wait_for_low().await;
+                    Ok(())
+                }
+                None => loop {
+                    trace!("polling for conversion to complete...");
+                    Timer::after(period / 4).await;
+
+                    if Self::read_config(&mut self.i2c, self.addr)
+                        .await?
+                        .op_status()
+                    {
+                        return Ok(());
+                    }
+                },
+            }
+        };
+
+        match with_timeout(period * 2, future).await {
+            Ok(Ok(())) => Ok(Self::read_result(&mut self.i2c, self.addr).await?),
+            Ok(Err(e)) => Err(Ads1115Error::Bus(e)),
+            Err(_) => Err(Self::lagged(&mut self.i2c, self.addr, config).await),
+        }
+    }
+
+    fn configure(
+        &self,
+        source: Source,
+        settings: Settings,
+    ) -> Result<Config, Ads1115Error<T::Error>> {
+        if !self.model.supports(source) {
+            return Err(Ads1115Error::Unsupported);
+        }
+
+        let mut config = self.config.clone();
+        config.set_data_rate(self.model.data_rate(settings.samples_per_second));
+        config.set_op_amp_gain(self.gain(source, settings.gain));
+        config.set_input_mux(source.into());
+        Ok(config)
+    }
+
+    fn saturated(&self, result: i16) -> bool {
+        let lsb = 1 << (16 - self.model.resolution());
+        result >= i16::MAX - (lsb - 1) || result == i16::MIN
+    }
+
+    fn autorange(
+        &mut self,
+        source: Source,
+        gain: OpAmpGain,
+        saturated: bool,
+        voltage: I8F24,
+    ) -> bool {
+        if !self.model.has_pga() {
+            return false;
+        }
+
+        if saturated {
+            if let Some(wider) = gain.wider() {
+                debug!("input {} saturated at gain {}", source, gain);
+                self.gains[index(source)] = wider;
+                return true;
+            }
+        }
+
+        let mut next = gain;
+        while let Some(narrower) = next.narrower() {
+            let full_scale = I8F24::from(narrower);
+            if voltage.abs() >= full_scale - (full_scale >> 3) {
+                break;
+            }
+            next = narrower;
+        }
+
+        if next != gain {
+            debug!("input {} ranged from gain {} to {}", source, gain, next);
+            self.gains[index(source)] = next;
+        }
+
+        false
+    }
+
+    fn gain(&self, source: Source, gain: Gain) -> OpAmpGain {
+        match gain {
+            Gain::Fixed(gain) if self.model.has_pga() => gain,
+            _ => self.gains[index(source)],
+        }
+    }
+
+    fn default_gain(model: Model) -> OpAmpGain {
+        if model.has_pga() {
+            OpAmpGain::Upto4V096
+        } else {
+            OpAmpGain::Upto2V048
+        }
+    }
+
+    fn period(&self, config: &Config) -> Duration {
+        let hz = self.model.samples_per_second(config.data_rate());
+        Duration::from_hz(hz)
+    }
+
+    async fn lagged(i2c: &mut T, addr: Addr, expected: Config) -> Ads1115Error<T::Error> {
+        match Self::read_config(i2c, addr).await {
+            Ok(config) if Self::matches(&config, &expected) => Ads1115Error::Timeout,
+            Ok(_) => Ads1115Error::ConfigMismatch,
+            Err(e) => Ads1115Error::Bus(e),
+        }
+    }
+
+    fn matches(config: &Config, expected: &Config) -> bool {
+        const OP_STATUS: u16 = 1 << 15;
+        u16::from(config.clone()) | OP_STATUS == u16::from(expected.clone()) | OP_STATUS
     }
 
     async fn read_result(i2c: &mut T, addr: Addr) -> Result<i16, T::Error> {
@@ -93,13 +423,83 @@ impl<'a, T: I2c> Ads1115<'a, T> {
     }
 }
 
-impl From<Channel> for InputMux {
-    fn from(value: Channel) -> Self {
+impl<T: I2c> Conversions<'_, '_, T> {
+    pub async fn next_voltage(&mut self) -> Result<I8F24, Ads1115Error<T::Error>> {
+        let result = self.next_result().await?;
+        Ok(i32::from(result) * self.unit)
+    }
+
+    pub async fn next(&mut self) -> Result<i16, Ads1115Error<T::Error>> {
+        let result = self.next_result().await?;
+        Ok(result >> (16 - self.ads1115.model.resolution()))
+    }
+
+    pub async fn stop(mut self) -> Result<(), Ads1115Error<T::Error>> {
+        self.config.set_op_mode(OperateMode::SingleShot);
+        self.ads1115.restore(self.config).await
+    }
+
+    async fn sum(&mut self, ratio: u16) -> Result<(i64, bool), Ads1115Error<T::Error>> {
+        let mut sum = 0i64;
+        let mut saturated = false;
+        for _ in 0..ratio {
+            let result = self.next_result().await?;
+            saturated |= self.ads1115.saturated(result);
+            sum += i64::from(result);
+        }
+
+        Ok((sum, saturated))
+    }
+
+    async fn next_result(&mut self) -> Result<i16, Ads1115Error<T::Error>> {
+        while self.discard > 0 {
+            self.discard -= 1;
+            self.wait_result().await?;
+        }
+
+        self.wait_result().await
+    }
+
+    async fn wait_result(&mut self) -> Result<i16, Ads1115Error<T::Error>> {
+        let Ads1115 { i2c, addr, rdy, .. } = &mut *self.ads1115;
+
+        match rdy {
+            Some(rdy) => {
+                trace!("waiting for pulse on ALERT/RDY pin...");
+
+                let future = rdy.wait_for_falling_edge();
+                if with_timeout(self.period * 2, future).await.is_err() {
+                    // OS reads busy for as long as continuous mode runs, so a missed
+                    // pulse is confirmed by the config readback and the latest result used.
+                    trace!("missed pulse on ALERT/RDY pin, polling config...");
+                    match Ads1115::lagged(i2c, *addr, self.config.clone()).await {
+                        Ads1115Error::Timeout => {}
+                        e => return Err(e),
+                    }
+                }
+            }
+            None => Timer::after(self.period).await,
+        }
+
+        Ok(Ads1115::read_result(i2c, *addr).await?)
+    }
+}
+
+fn index(source: Source) -> usize {
+    usize::from(u3::from(InputMux::from(source)).value())
+}
+
+impl From<Source> for InputMux {
+    fn from(value: Source) -> Self {
         match value {
-            Channel::A0 => InputMux::A0Gnd,
-            Channel::A1 => InputMux::A1Gnd,
-            Channel::A2 => InputMux::A2Gnd,
-            Channel::A3 => InputMux::A3Gnd,
+            Source::Single(Channel::A0) => InputMux::A0Gnd,
+            Source::Single(Channel::A1) => InputMux::A1Gnd,
+            Source::Single(Channel::A2) => InputMux::A2Gnd,
+            Source::Single(Channel::A3) => InputMux::A3Gnd,
+            Source::Differential(Pair::A0A1) => InputMux::A0A1,
+            Source::Differential(Pair::A0A3) => InputMux::A0A3,
+            Source::Differential(Pair::A1A3) => InputMux::A1A3,
+            Source::Differential(Pair::A2A3) => InputMux::A2A3,
         }
     }
 }
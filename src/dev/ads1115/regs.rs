@@ -1,4 +1,5 @@
 use bilge::prelude::*;
+use defmt::Format;
 use fixed::types::I8F24;
 use fixed_macro::types::I8F24;
 
@@ -27,7 +28,7 @@ pub enum InputMux {
 }
 
 #[bitsize(3)]
-#[derive(FromBits, Debug)]
+#[derive(FromBits, Debug, Clone, Copy, PartialEq, Format)]
 pub enum OpAmpGain {
     Upto6V144,
     Upto4V096,
@@ -90,6 +91,34 @@ pub enum CompareQueue {
     DisableCompare,
 }
 
+impl Config {
+    pub const DEFAULT: u16 = 0x8583;
+}
+
+impl OpAmpGain {
+    pub fn wider(self) -> Option<Self> {
+        match self {
+            Self::Upto6V144 => None,
+            Self::Upto4V096 => Some(Self::Upto6V144),
+            Self::Upto2V048 => Some(Self::Upto4V096),
+            Self::Upto1V024 => Some(Self::Upto2V048),
+            Self::Upto0V512 => Some(Self::Upto1V024),
+            Self::Upto0V256 => Some(Self::Upto0V512),
+        }
+    }
+
+    pub fn narrower(self) -> Option<Self> {
+        match self {
+            Self::Upto6V144 => Some(Self::Upto4V096),
+            Self::Upto4V096 => Some(Self::Upto2V048),
+            Self::Upto2V048 => Some(Self::Upto1V024),
+            Self::Upto1V024 => Some(Self::Upto0V512),
+            Self::Upto0V512 => Some(Self::Upto0V256),
+            Self::Upto0V256 => None,
+        }
+    }
+}
+
 impl From<OpAmpGain> for I8F24 {
     fn from(value: OpAmpGain) -> Self {
         match value {
@@ -102,18 +131,3 @@ impl From<OpAmpGain> for I8F24 {
         }
     }
 }
-
-impl From<DataRate> for u64 {
-    fn from(value: DataRate) -> Self {
-        match value {
-            DataRate::Sps8 => 8,
-            DataRate::Sps16 => 16,
-            DataRate::Sps32 => 32,
-            DataRate::Sps64 => 64,
-            DataRate::Sps128 => 128,
-            DataRate::Sps250 => 250,
-            DataRate::Sps475 => 475,
-            DataRate::Sps860 => 860,
-        }
-    }
-}
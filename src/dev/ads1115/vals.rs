@@ -1,6 +1,9 @@
+use bilge::prelude::*;
 use core::fmt;
 use defmt::Format;
 
+use super::regs::{DataRate, OpAmpGain};
+
 pub struct Reg();
 
 #[derive(Clone, Copy)]
@@ -12,7 +15,17 @@ pub enum Addr {
     Scl = Addr::Gnd as u8 + 3,
 }
 
-#[derive(Clone, Copy, Format)]
+#[derive(Clone, Copy, PartialEq, Format)]
+pub enum Model {
+    Ads1013,
+    Ads1014,
+    Ads1015,
+    Ads1113,
+    Ads1114,
+    Ads1115,
+}
+
+#[derive(Clone, Copy, PartialEq, Format)]
 pub enum Channel {
     A0,
     A1,
@@ -20,6 +33,40 @@ pub enum Channel {
     A3,
 }
 
+#[derive(Clone, Copy, PartialEq, Format)]
+pub enum Pair {
+    A0A1,
+    A0A3,
+    A1A3,
+    A2A3,
+}
+
+#[derive(Clone, Copy, PartialEq, Format)]
+pub enum Source {
+    Single(Channel),
+    Differential(Pair),
+}
+
+#[derive(Clone, Copy, PartialEq, Format)]
+pub enum Gain {
+    Auto,
+    Fixed(OpAmpGain),
+}
+
+#[derive(Clone, Copy, Format)]
+pub struct Settings {
+    pub gain: Gain,
+    pub samples_per_second: u64,
+    pub discard: u8,
+}
+
+pub enum Ads1115Error<E> {
+    Bus(E),
+    Timeout,
+    ConfigMismatch,
+    Unsupported,
+}
+
 impl Reg {
     pub const RESULT: [u8; 1] = [0];
     pub const CONFIG: [u8; 1] = [1];
@@ -38,6 +85,79 @@ impl Format for Addr {
     }
 }
 
+impl Settings {
+    pub const DEFAULT: Self = Self {
+        gain: Gain::Auto,
+        samples_per_second: 64,
+        discard: 0,
+    };
+}
+
+impl Model {
+    pub fn resolution(self) -> u32 {
+        match self {
+            Self::Ads1013 | Self::Ads1014 | Self::Ads1015 => 12,
+            Self::Ads1113 | Self::Ads1114 | Self::Ads1115 => 16,
+        }
+    }
+
+    pub fn channels(self) -> usize {
+        match self {
+            Self::Ads1013 | Self::Ads1014 | Self::Ads1113 | Self::Ads1114 => 1,
+            Self::Ads1015 | Self::Ads1115 => 4,
+        }
+    }
+
+    pub fn has_pga(self) -> bool {
+        !matches!(self, Self::Ads1013 | Self::Ads1113)
+    }
+
+    pub fn has_comparator(self) -> bool {
+        !matches!(self, Self::Ads1013 | Self::Ads1113)
+    }
+
+    pub fn supports(self, source: Source) -> bool {
+        match self.channels() {
+            1 => matches!(source, Source::Differential(Pair::A0A1)),
+            _ => true,
+        }
+    }
+
+    pub fn samples_per_second(self, data_rate: DataRate) -> u64 {
+        self.data_rates()[usize::from(u3::from(data_rate).value())]
+    }
+
+    pub fn data_rate(self, samples_per_second: u64) -> DataRate {
+        let rates = self.data_rates();
+        let index = rates.iter().position(|&sps| sps >= samples_per_second);
+        DataRate::from(u3::new(index.unwrap_or(rates.len() - 1) as u8))
+    }
+
+    fn data_rates(self) -> [u64; 8] {
+        match self.resolution() {
+            12 => [128, 250, 490, 920, 1600, 2400, 3300, 3300],
+            _ => [8, 16, 32, 64, 128, 250, 475, 860],
+        }
+    }
+}
+
+impl<E: Format> Format for Ads1115Error<E> {
+    fn format(&self, fmt: defmt::Formatter) {
+        match self {
+            Self::Bus(e) => defmt::write!(fmt, "bus error: {}", e),
+            Self::Timeout => defmt::write!(fmt, "conversion timed out"),
+            Self::ConfigMismatch => defmt::write!(fmt, "config readback mismatch"),
+            Self::Unsupported => defmt::write!(fmt, "not supported by device model"),
+        }
+    }
+}
+
+impl<E> From<E> for Ads1115Error<E> {
+    fn from(value: E) -> Self {
+        Self::Bus(value)
+    }
+}
+
 impl fmt::Display for Addr {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
@@ -59,3 +179,35 @@ impl fmt::Display for Channel {
         }
     }
 }
+
+impl fmt::Display for Pair {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::A0A1 => write!(f, "A0-A1"),
+            Self::A0A3 => write!(f, "A0-A3"),
+            Self::A1A3 => write!(f, "A1-A3"),
+            Self::A2A3 => write!(f, "A2-A3"),
+        }
+    }
+}
+
+impl fmt::Display for Source {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Single(channel) => channel.fmt(f),
+            Self::Differential(pair) => pair.fmt(f),
+        }
+    }
+}
+
+impl From<Channel> for Source {
+    fn from(value: Channel) -> Self {
+        Self::Single(value)
+    }
+}
+
+impl From<Pair> for Source {
+    fn from(value: Pair) -> Self {
+        Self::Differential(value)
+    }
+}
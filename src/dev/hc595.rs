@@ -0,0 +1,54 @@
+use embedded_hal_async::spi::SpiDevice;
+
+pub struct Hc595<S, const N: usize = 1> {
+    spi: S,
+    outputs: [u8; N],
+}
+
+pub enum Error<E> {
+    Spi(E),
+    OutOfRange,
+}
+
+impl<S: SpiDevice, const N: usize> Hc595<S, N> {
+    pub const PINS: usize = N * 8;
+
+    pub fn new(spi: S) -> Self {
+        Self {
+            spi,
+            outputs: [0; N],
+        }
+    }
+
+    pub async fn set(&mut self, pin: usize, high: bool) -> Result<(), Error<S::Error>> {
+        if pin >= Self::PINS {
+            return Err(Error::OutOfRange);
+        }
+
+        let (byte, bit) = (pin / 8, pin % 8);
+        if high {
+            self.outputs[byte] |= 1 << bit;
+        } else {
+            self.outputs[byte] &= !(1 << bit);
+        }
+
+        Ok(self.latch().await?)
+    }
+
+    pub async fn clear(&mut self) -> Result<(), S::Error> {
+        self.outputs = [0; N];
+        self.latch().await
+    }
+
+    async fn latch(&mut self) -> Result<(), S::Error> {
+        let mut bytes = self.outputs;
+        bytes.reverse();
+        self.spi.write(&bytes).await
+    }
+}
+
+impl<E> From<E> for Error<E> {
+    fn from(value: E) -> Self {
+        Self::Spi(value)
+    }
+}
@@ -0,0 +1,178 @@
+use defmt::{warn, Format};
+use embassy_rp::gpio::{Level, OutputOpenDrain};
+use embassy_rp::i2c::{self, AbortReason, Async, Config, Instance, InterruptHandler};
+use embassy_rp::i2c::{SclPin, SdaPin};
+use embassy_rp::interrupt::typelevel::Binding;
+use embassy_rp::{into_ref, Peripheral, PeripheralRef};
+use embassy_time::{with_timeout, Duration, TimeoutError, Timer};
+use embedded_hal_async::i2c::{ErrorKind, ErrorType, I2c, Operation};
+
+pub struct I2cBus<'d, T: Instance + Peripheral<P = T>, C: SclPin<T>, D: SdaPin<T>, I> {
+    i2c: i2c::I2c<'d, T, Async>,
+    peri: PeripheralRef<'d, T>,
+    scl: PeripheralRef<'d, C>,
+    sda: PeripheralRef<'d, D>,
+    irqs: I,
+    config: Config,
+}
+
+#[derive(Clone, Copy, Debug, Format)]
+pub enum Error {
+    I2c(i2c::Error),
+    Timeout,
+}
+
+impl<'d, T, C, D, I> I2cBus<'d, T, C, D, I>
+where
+    T: Instance + Peripheral<P = T>,
+    C: SclPin<T>,
+    D: SdaPin<T>,
+    I: Binding<T::Interrupt, InterruptHandler<T>> + Copy,
+{
+    const TIMEOUT: Duration = Duration::from_millis(25);
+
+    pub fn new(
+        peri: impl Peripheral<P = T> + 'd,
+        scl: impl Peripheral<P = C> + 'd,
+        sda: impl Peripheral<P = D> + 'd,
+        irqs: I,
+        config: Config,
+    ) -> Self {
+        into_ref!(peri, scl, sda);
+
+        // SAFETY: the driver is the only user of the peripheral and its pins,
+        // except during `recover`, which replaces the driver afterwards.
+        let i2c = unsafe {
+            i2c::I2c::new_async(
+                peri.clone_unchecked(),
+                scl.clone_unchecked(),
+                sda.clone_unchecked(),
+                irqs,
+                config,
+            )
+        };
+
+        Self {
+            i2c,
+            peri,
+            scl,
+            sda,
+            irqs,
+            config,
+        }
+    }
+
+    pub async fn recover(&mut self) {
+        warn!("clearing I2C bus...");
+
+        // SAFETY: the driver is not used until it is replaced below.
+        let mut scl = OutputOpenDrain::new(unsafe { self.scl.clone_unchecked() }, Level::High);
+        let mut sda = OutputOpenDrain::new(unsafe { self.sda.clone_unchecked() }, Level::High);
+
+        for _ in 0..9 {
+            if sda.is_high() {
+                break;
+            }
+
+            scl.set_low();
+            Timer::after_micros(5).await;
+            scl.set_high();
+            Timer::after_micros(5).await;
+        }
+
+        sda.set_low();
+        Timer::after_micros(5).await;
+        sda.set_high();
+        Timer::after_micros(5).await;
+
+        drop((scl, sda));
+
+        // SAFETY: the pins are released again and the old driver holds no state.
+        self.i2c = unsafe {
+            i2c::I2c::new_async(
+                self.peri.clone_unchecked(),
+                self.scl.clone_unchecked(),
+                self.sda.clone_unchecked(),
+                self.irqs,
+                self.config,
+            )
+        };
+    }
+
+    async fn check(
+        &mut self,
+        result: Result<Result<(), i2c::Error>, TimeoutError>,
+    ) -> Result<(), Error> {
+        let error = match result {
+            Ok(Ok(())) => return Ok(()),
+            Ok(Err(e)) => Error::I2c(e),
+            Err(TimeoutError) => Error::Timeout,
+        };
+
+        if let Error::Timeout
+        | Error::I2c(i2c::Error::Abort(AbortReason::ArbitrationLoss))
+        | Error::I2c(i2c::Error::Abort(AbortReason::Other(_))) = error
+        {
+            self.recover().await;
+        }
+
+        Err(error)
+    }
+}
+
+impl embedded_hal_async::i2c::Error for Error {
+    fn kind(&self) -> ErrorKind {
+        match self {
+            Self::I2c(e) => e.kind(),
+            Self::Timeout => ErrorKind::Other,
+        }
+    }
+}
+
+impl<T, C, D, I> ErrorType for I2cBus<'_, T, C, D, I>
+where
+    T: Instance + Peripheral<P = T>,
+    C: SclPin<T>,
+    D: SdaPin<T>,
+{
+    type Error = Error;
+}
+
+impl<T, C, D, I> I2c for I2cBus<'_, T, C, D, I>
+where
+    T: Instance + Peripheral<P = T>,
+    C: SclPin<T>,
+    D: SdaPin<T>,
+    I: Binding<T::Interrupt, InterruptHandler<T>> + Copy,
+{
+    async fn read(&mut self, address: u8, read: &mut [u8]) -> Result<(), Self::Error> {
+        let result = with_timeout(Self::TIMEOUT, self.i2c.read(address, read)).await;
+        self.check(result).await
+    }
+
+    async fn write(&mut self, address: u8, write: &[u8]) -> Result<(), Self::Error> {
+        let result = with_timeout(Self::TIMEOUT, self.i2c.write(address, write)).await;
+        self.check(result).await
+    }
+
+    async fn write_read(
+        &mut self,
+        address: u8,
+        write: &[u8],
+        read: &mut [u8],
+    ) -> Result<(), Self::Error> {
+        let future = self.i2c.write_read(address, write, read);
+        let result = with_timeout(Self::TIMEOUT, future).await;
+        self.check(result).await
+    }
+
+    async fn transaction(
+        &mut self,
+        address: u8,
+        operations: &mut [Operation<'_>],
+    ) -> Result<(), Self::Error> {
+        let future = self.i2c.transaction(address, operations);
+        let result = with_timeout(Self::TIMEOUT, future).await;
+        self.check(result).await
+    }
+}
@@ -0,0 +1,80 @@
+use embedded_hal_async::i2c::I2c;
+
+pub struct Mcp23017<T> {
+    i2c: T,
+    addr: u8,
+    outputs: u16,
+    ready: bool,
+}
+
+pub enum Error<E> {
+    Bus(E),
+    OutOfRange,
+}
+
+struct Reg;
+
+impl Reg {
+    const IODIRA: [u8; 1] = [0x00];
+    const OLATA: [u8; 1] = [0x14];
+}
+
+impl<T: I2c> Mcp23017<T> {
+    pub const PINS: usize = 16;
+
+    pub fn new(i2c: T, addr: u8) -> Self {
+        Self {
+            i2c,
+            addr: 0x20 | (addr & 0x07),
+            outputs: 0,
+            ready: false,
+        }
+    }
+
+    pub async fn init(&mut self) -> Result<(), T::Error> {
+        self.outputs = 0;
+        self.ready = false;
+        self.latch().await
+    }
+
+    pub async fn set(&mut self, pin: usize, high: bool) -> Result<(), Error<T::Error>> {
+        if pin >= Self::PINS {
+            return Err(Error::OutOfRange);
+        }
+
+        if high {
+            self.outputs |= 1 << pin;
+        } else {
+            self.outputs &= !(1 << pin);
+        }
+
+        Ok(self.latch().await?)
+    }
+
+    pub async fn clear(&mut self) -> Result<(), T::Error> {
+        self.outputs = 0;
+        self.latch().await
+    }
+
+    async fn latch(&mut self) -> Result<(), T::Error> {
+        self.write(Reg::OLATA, self.outputs).await?;
+
+        if !self.ready {
+            self.write(Reg::IODIRA, 0x0000).await?;
+            self.ready = true;
+        }
+
+        Ok(())
+    }
+
+    async fn write(&mut self, reg: [u8; 1], value: u16) -> Result<(), T::Error> {
+        let [a, b] = value.to_le_bytes();
+        self.i2c.write(self.addr, &[reg[0], a, b]).await
+    }
+}
+
+impl<E> From<E> for Error<E> {
+    fn from(value: E) -> Self {
+        Self::Bus(value)
+    }
+}
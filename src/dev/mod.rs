@@ -1,3 +1,7 @@
+pub use pumpedli_core::cd4067;
+
 pub mod ads1115;
-pub mod cd4067;
+pub mod hc595;
+pub mod i2c;
+pub mod mcp23017;
 pub mod ws2812;
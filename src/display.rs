@@ -28,10 +28,10 @@ impl<'a, T: SpiDevice> Dashboard<'a, T> {
 
     pub async fn run(&mut self) -> ! {
         loop {
-            use ReadingResult::{Err, Ok};
+            use ReadingResult::{Err, Fault, Ok};
 
             let reading = self.subscriber.next_message_pure().await;
-            let Reading::Moisture(control_loop, result) = reading else {
+            let Reading::Moisture(control_loop, result, _) = reading else {
                 continue;
             };
 
@@ -44,6 +44,7 @@ impl<'a, T: SpiDevice> Dashboard<'a, T> {
                 Err(ValueOutOfRange::Under(_)) => Lcd199::with_value(position, i32::MIN),
                 Err(ValueOutOfRange::Over(_)) => Lcd199::with_value(position, i32::MAX),
                 Err(ValueOutOfRange::None) => Lcd199::new(position),
+                Fault(_) => Lcd199::new(position),
             };
 
             if let Result::Err(e) = lcd.draw(&mut self.display) {
@@ -0,0 +1,80 @@
+use embassy_futures::select::{select, Either};
+use embassy_rp::gpio::Input;
+use embassy_time::{Duration, Ticker};
+use portable_atomic::{AtomicU32, Ordering};
+
+use crate::reading::{Reading, SupplyPublisher};
+
+pub struct Counter {
+    pulses: AtomicU32,
+    pulses_per_liter: u32,
+}
+
+pub struct Meter<'a> {
+    input: Input<'a>,
+    counter: &'a Counter,
+    publisher: SupplyPublisher<'a>,
+}
+
+impl Counter {
+    pub const fn new(pulses_per_liter: u32) -> Self {
+        Self {
+            pulses: AtomicU32::new(0),
+            pulses_per_liter,
+        }
+    }
+
+    pub fn pulses(&self) -> u32 {
+        self.pulses.load(Ordering::Relaxed)
+    }
+
+    pub fn milliliters(&self) -> u32 {
+        (u64::from(self.pulses()) * 1000 / u64::from(self.pulses_per_liter)) as u32
+    }
+
+    fn pulse(&self) {
+        self.pulses.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+impl<'a> Meter<'a> {
+    pub fn new(input: Input<'a>, counter: &'a Counter, publisher: SupplyPublisher<'a>) -> Self {
+        Self {
+            input,
+            counter,
+            publisher,
+        }
+    }
+
+    pub async fn run(mut self) -> ! {
+        const PERIOD: Duration = Duration::from_secs(1);
+
+        let mut ticker = Ticker::every(PERIOD);
+        let mut last = self.counter.milliliters();
+        let mut result = None;
+
+        loop {
+            let future = select(self.input.wait_for_falling_edge(), ticker.next());
+            if let Either::First(()) = future.await {
+                self.counter.pulse();
+                continue;
+            }
+
+            let total = self.counter.milliliters();
+            let rate = total.wrapping_sub(last) * 60;
+            last = total;
+
+            if result
+                .replace((rate, total))
+                .is_some_and(|r| r == (rate, total))
+            {
+                continue;
+            }
+
+            self.publisher.publish_immediate(Reading::Flow {
+                milliliters_per_minute: rate,
+                milliliters: total,
+            });
+        }
+    }
+}
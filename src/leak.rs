@@ -0,0 +1,110 @@
+use defmt::{error, trace, warn, Format};
+use embassy_rp::gpio::Input;
+use embassy_sync::blocking_mutex::raw::{CriticalSectionRawMutex, NoopRawMutex};
+use embassy_sync::mutex::Mutex;
+use embassy_sync::signal::Signal;
+use embassy_time::{Duration, Instant, Timer};
+use palette::{named, GetHue, Srgb};
+
+use crate::actuator::{Actuator, Pump};
+use crate::lockout::{self, Lockout};
+use crate::{flow, mux, rgb, watchdog};
+
+pub struct Detection {
+    pub settle: Duration,
+    pub pulses: u32,
+}
+
+pub struct Monitor<'a, P, V> {
+    control_mutex: &'a Mutex<NoopRawMutex, Actuator<'a, P, V>>,
+    flow: &'a flow::Counter,
+    detection: Detection,
+    lockout: &'a Lockout,
+    acknowledge: Input<'a>,
+    alarm: &'a Signal<CriticalSectionRawMutex, Option<rgb::Mode>>,
+    heartbeat: &'a watchdog::Heartbeat,
+}
+
+impl<'a, P: Pump, V: mux::Valves<Error: Format>> Monitor<'a, P, V> {
+    pub fn new(
+        control_mutex: &'a Mutex<NoopRawMutex, Actuator<'a, P, V>>,
+        flow: &'a flow::Counter,
+        detection: Detection,
+        lockout: &'a Lockout,
+        acknowledge: Input<'a>,
+        alarm: &'a Signal<CriticalSectionRawMutex, Option<rgb::Mode>>,
+        heartbeat: &'a watchdog::Heartbeat,
+    ) -> Self {
+        Self {
+            control_mutex,
+            flow,
+            detection,
+            lockout,
+            acknowledge,
+            alarm,
+            heartbeat,
+        }
+    }
+
+    pub async fn run(self) -> ! {
+        const PERIOD: Duration = Duration::from_secs(1);
+
+        let mut window: Option<(Instant, u32)> = None;
+
+        loop {
+            self.heartbeat.check_in();
+            Timer::after(PERIOD).await;
+
+            if self.lockout.is_engaged(lockout::Reason::Leak) {
+                if self.acknowledge.is_low() {
+                    warn!("leak alarm acknowledged, releasing lockout");
+                    self.lockout.release(lockout::Reason::Leak);
+                    self.alarm.signal(None);
+                }
+
+                window = None;
+                continue;
+            }
+
+            let Ok(mut actuator) = self.control_mutex.try_lock() else {
+                window = None;
+                continue;
+            };
+
+            let idle = actuator.idle_since();
+            if idle.elapsed() < self.detection.settle {
+                window = None;
+                continue;
+            }
+
+            let pulses = self.flow.pulses();
+            let Some((since, start)) = window.filter(|&(since, _)| since == idle) else {
+                trace!("watching for flow while idle...");
+                window = Some((idle, pulses));
+                continue;
+            };
+
+            let leaked = pulses.wrapping_sub(start);
+            if leaked < self.detection.pulses {
+                continue;
+            }
+
+            error!(
+                "leak detected: {} pulses while idle since {}",
+                leaked, since
+            );
+            log::error!("leak detected: {leaked} pulses while all valves are closed");
+
+            self.lockout.engage(lockout::Reason::Leak);
+            actuator.shut_off().await;
+
+            self.alarm.signal(Some(rgb::Mode::Breathe {
+                period: Duration::from_secs(1),
+                hue: Srgb::<f32>::from(named::RED).get_hue(),
+                value: 0.3,
+            }));
+
+            window = None;
+        }
+    }
+}
@@ -1,12 +1,20 @@
 #![no_std]
 
+pub use pumpedli_core::{filter, health, scaling};
+
+pub mod actuator;
 pub mod adc;
 pub mod control;
 pub mod dev;
 pub mod display;
+pub mod flow;
+pub mod leak;
 pub mod led;
+pub mod lockout;
 pub mod mux;
 pub mod program;
 pub mod reading;
+pub mod reservoir;
 pub mod rgb;
-pub mod scaling;
+pub mod safe_state;
+pub mod watchdog;
@@ -0,0 +1,51 @@
+use defmt::Format;
+use portable_atomic::{AtomicU8, Ordering};
+
+#[derive(Clone, Copy, PartialEq, Format)]
+pub enum Reason {
+    Leak,
+    Reservoir,
+}
+
+pub struct Lockout {
+    reasons: AtomicU8,
+}
+
+impl Reason {
+    const ALL: [Self; 2] = [Self::Leak, Self::Reservoir];
+
+    fn bit(self) -> u8 {
+        1 << self as u8
+    }
+}
+
+impl Lockout {
+    pub const fn new() -> Self {
+        Self {
+            reasons: AtomicU8::new(0),
+        }
+    }
+
+    pub fn engage(&self, reason: Reason) {
+        self.reasons.fetch_or(reason.bit(), Ordering::Relaxed);
+    }
+
+    pub fn release(&self, reason: Reason) {
+        self.reasons.fetch_and(!reason.bit(), Ordering::Relaxed);
+    }
+
+    pub fn is_engaged(&self, reason: Reason) -> bool {
+        self.reasons.load(Ordering::Relaxed) & reason.bit() != 0
+    }
+
+    pub fn engaged(&self) -> Option<Reason> {
+        let reasons = self.reasons.load(Ordering::Relaxed);
+        Reason::ALL.into_iter().find(|r| reasons & r.bit() != 0)
+    }
+}
+
+impl Default for Lockout {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -1,4 +1,156 @@
-use crate::dev::cd4067::Channel;
+use bilge::prelude::*;
+use defmt::Format;
+use embedded_hal::digital::OutputPin;
+use embedded_hal_async::i2c::I2c;
+use embedded_hal_async::spi::SpiDevice;
+
+use crate::dev::cd4067::{self, Cd4067, Channel};
+use crate::dev::hc595::{self, Hc595};
+use crate::dev::mcp23017::{self, Mcp23017};
 
 #[derive(PartialEq)]
-pub struct Output(pub Channel);
+pub struct Output(pub usize);
+
+pub struct Gpio<P, const N: usize>(pub [P; N]);
+
+pub enum Error<E> {
+    Driver(E),
+    OutOfRange,
+}
+
+#[allow(async_fn_in_trait)]
+pub trait Valves {
+    type Error;
+
+    const CONCURRENT: bool;
+
+    async fn open(&mut self, valve: usize) -> Result<(), Self::Error>;
+
+    async fn close(&mut self, valve: usize) -> Result<(), Self::Error>;
+
+    async fn close_all(&mut self) -> Result<(), Self::Error>;
+}
+
+impl<E> From<E> for Error<E> {
+    fn from(value: E) -> Self {
+        Self::Driver(value)
+    }
+}
+
+impl<E: Format> Format for Error<E> {
+    fn format(&self, fmt: defmt::Formatter) {
+        match self {
+            Self::Driver(e) => defmt::write!(fmt, "driver error: {}", e),
+            Self::OutOfRange => defmt::write!(fmt, "valve out of range"),
+        }
+    }
+}
+
+impl<P: OutputPin, const N: usize> Valves for Cd4067<P, N> {
+    type Error = Error<P::Error>;
+
+    const CONCURRENT: bool = false;
+
+    async fn open(&mut self, valve: usize) -> Result<(), Self::Error> {
+        let (chip, channel) = (valve / 16, valve % 16);
+        let channel = Channel::from(u4::new(channel as u8));
+
+        match self.enable(chip, channel) {
+            Ok(()) => Ok(()),
+            Err(cd4067::Error::Pin(e)) => Err(Error::Driver(e)),
+            Err(cd4067::Error::OutOfRange) => Err(Error::OutOfRange),
+        }
+    }
+
+    async fn close(&mut self, valve: usize) -> Result<(), Self::Error> {
+        let Some((chip, channel)) = self.enabled() else {
+            return Ok(());
+        };
+
+        if chip * 16 + usize::from(u4::from(channel).value()) != valve {
+            return Ok(());
+        }
+
+        Ok(self.disable()?)
+    }
+
+    async fn close_all(&mut self) -> Result<(), Self::Error> {
+        Ok(self.disable()?)
+    }
+}
+
+impl<P: OutputPin, const N: usize> Valves for Gpio<P, N> {
+    type Error = Error<P::Error>;
+
+    const CONCURRENT: bool = true;
+
+    async fn open(&mut self, valve: usize) -> Result<(), Self::Error> {
+        let pin = self.0.get_mut(valve).ok_or(Error::OutOfRange)?;
+        Ok(pin.set_high()?)
+    }
+
+    async fn close(&mut self, valve: usize) -> Result<(), Self::Error> {
+        let pin = self.0.get_mut(valve).ok_or(Error::OutOfRange)?;
+        Ok(pin.set_low()?)
+    }
+
+    async fn close_all(&mut self) -> Result<(), Self::Error> {
+        for pin in &mut self.0 {
+            pin.set_low()?;
+        }
+
+        Ok(())
+    }
+}
+
+impl<S: SpiDevice, const N: usize> Valves for Hc595<S, N> {
+    type Error = Error<S::Error>;
+
+    const CONCURRENT: bool = true;
+
+    async fn open(&mut self, valve: usize) -> Result<(), Self::Error> {
+        match self.set(valve, true).await {
+            Ok(()) => Ok(()),
+            Err(hc595::Error::Spi(e)) => Err(Error::Driver(e)),
+            Err(hc595::Error::OutOfRange) => Err(Error::OutOfRange),
+        }
+    }
+
+    async fn close(&mut self, valve: usize) -> Result<(), Self::Error> {
+        match self.set(valve, false).await {
+            Ok(()) => Ok(()),
+            Err(hc595::Error::Spi(e)) => Err(Error::Driver(e)),
+            Err(hc595::Error::OutOfRange) => Err(Error::OutOfRange),
+        }
+    }
+
+    async fn close_all(&mut self) -> Result<(), Self::Error> {
+        Ok(self.clear().await?)
+    }
+}
+
+impl<T: I2c> Valves for Mcp23017<T> {
+    type Error = Error<T::Error>;
+
+    const CONCURRENT: bool = true;
+
+    async fn open(&mut self, valve: usize) -> Result<(), Self::Error> {
+        match self.set(valve, true).await {
+            Ok(()) => Ok(()),
+            Err(mcp23017::Error::Bus(e)) => Err(Error::Driver(e)),
+            Err(mcp23017::Error::OutOfRange) => Err(Error::OutOfRange),
+        }
+    }
+
+    async fn close(&mut self, valve: usize) -> Result<(), Self::Error> {
+        match self.set(valve, false).await {
+            Ok(()) => Ok(()),
+            Err(mcp23017::Error::Bus(e)) => Err(Error::Driver(e)),
+            Err(mcp23017::Error::OutOfRange) => Err(Error::OutOfRange),
+        }
+    }
+
+    async fn close_all(&mut self) -> Result<(), Self::Error> {
+        Ok(self.clear().await?)
+    }
+}
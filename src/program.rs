@@ -1,15 +1,15 @@
-use core::future;
-
 use defmt::{trace, warn, Format};
+use embassy_futures::select::{select, Either};
 use embassy_sync::blocking_mutex::raw::CriticalSectionRawMutex;
 use embassy_sync::signal::Signal;
-use embassy_time::{Duration, Instant};
+use embassy_time::{with_timeout, Duration, Instant};
 use palette::{named, GetHue, Srgb};
 
 use crate::control::{Action, ActionPublisher};
-use crate::reading::{Reading, ReadingResult, ReadingSubscriber};
+use crate::health::Health;
+use crate::reading::{Diagnostic, Reading, ReadingResult, ReadingSubscriber, SupplySubscriber};
 use crate::scaling::ValueOutOfRange;
-use crate::{adc, led, rgb};
+use crate::{adc, led, lockout, rgb, watchdog};
 
 #[derive(Default)]
 pub struct Program(pub ProgramConfig, pub ProgramState);
@@ -19,6 +19,8 @@ pub struct ProgramConfig {
     pub high_threshold: i32,
     pub run_duration: Duration,
     pub pause_duration: Duration,
+    pub pump_duty: u8,
+    pub run_volume: Option<u32>,
 }
 
 #[derive(Default)]
@@ -34,16 +36,24 @@ pub enum ProgramState {
     },
 }
 
+#[derive(Clone, Copy, PartialEq)]
 #[non_exhaustive]
 pub enum ProgramFault {
     WaterNotRunning,
+    ValveFailure,
+    DryRun,
+    Blockage,
+    NoFlow,
+    LockedOut(lockout::Reason),
 }
 
 pub struct Regulator<'a> {
     subscriber: ReadingSubscriber<'a>,
+    supply: SupplySubscriber<'a>,
     publisher: ActionPublisher<'a>,
     led: &'a Signal<CriticalSectionRawMutex, led::Mode>,
     rgb: &'a Signal<CriticalSectionRawMutex, rgb::Mode>,
+    heartbeat: &'a watchdog::Heartbeat,
 }
 
 impl Default for ProgramConfig {
@@ -53,6 +63,8 @@ impl Default for ProgramConfig {
             high_threshold: 90,
             run_duration: Duration::from_secs(3),
             pause_duration: Duration::from_secs(120),
+            pump_duty: 100,
+            run_volume: None,
         }
     }
 }
@@ -61,6 +73,11 @@ impl Format for ProgramFault {
     fn format(&self, fmt: defmt::Formatter) {
         match self {
             Self::WaterNotRunning => defmt::write!(fmt, "water is not running"),
+            Self::ValveFailure => defmt::write!(fmt, "valve driver failed"),
+            Self::DryRun => defmt::write!(fmt, "pump is running dry"),
+            Self::Blockage => defmt::write!(fmt, "pump is blocked or stalled"),
+            Self::NoFlow => defmt::write!(fmt, "no water is flowing"),
+            Self::LockedOut(reason) => defmt::write!(fmt, "locked out: {}", reason),
         }
     }
 }
@@ -68,40 +85,84 @@ impl Format for ProgramFault {
 impl<'a> Regulator<'a> {
     pub fn new(
         subscriber: ReadingSubscriber<'a>,
+        supply: SupplySubscriber<'a>,
         publisher: ActionPublisher<'a>,
         led: &'a Signal<CriticalSectionRawMutex, led::Mode>,
         rgb: &'a Signal<CriticalSectionRawMutex, rgb::Mode>,
+        heartbeat: &'a watchdog::Heartbeat,
     ) -> Self {
         Self {
             subscriber,
+            supply,
             publisher,
             led,
             rgb,
+            heartbeat,
         }
     }
 
     pub async fn run(&mut self) -> ! {
         loop {
             use led::Mode::Off;
-            use rgb::Mode::Color;
 
-            let reading = self.subscriber.next_message_pure().await;
-            let Reading::Moisture(control_loop, result) = reading else {
-                continue;
+            self.heartbeat.check_in();
+
+            let future = select(
+                self.subscriber.next_message_pure(),
+                self.supply.next_message_pure(),
+            );
+            let reading = match with_timeout(watchdog::CHECK_IN, future).await {
+                Ok(Either::First(reading)) => reading,
+                Ok(Either::Second(supply)) => {
+                    Self::log_supply(&supply);
+                    continue;
+                }
+                Err(_) => continue,
             };
 
             let t_ms = Instant::now().as_millis();
-            let adc::Input(addr, channel) = *control_loop.adc_input;
+
+            if let Reading::Diagnostic(control_loop, ref diagnostic) = reading {
+                let adc::Input(addr, source, _) = *control_loop.adc_input;
+                let Diagnostic {
+                    code,
+                    voltage,
+                    ratio,
+                    filtered,
+                    samples,
+                    spread_percent,
+                } = *diagnostic;
+
+                log::info!(
+                    "{t_ms} ms; addr {addr}; input {source}; code {code}; voltage {voltage}; \
+                     ratio {ratio:?}; filtered {filtered:?}; samples {samples}; spread {spread_percent} %"
+                );
+                continue;
+            }
+
+            let Reading::Moisture(control_loop, result, health) = reading else {
+                continue;
+            };
+
+            let adc::Input(addr, source, _) = *control_loop.adc_input;
 
             match result {
                 ReadingResult::Ok(value) => {
-                    log::info!("{t_ms} ms; addr {addr}; channel {channel}; value {value}");
-                    trace!("addr {}; channel {}; value {}", addr, channel, value);
+                    log::info!("{t_ms} ms; addr {addr}; input {source}; value {value}");
+                    trace!("addr {}; input {}; value {}", addr, source, value);
                 }
                 ReadingResult::Err(e) => {
-                    log::info!("{t_ms} ms; addr {addr}; channel {channel}; {e}");
-                    trace!("addr {}; channel {}; no value", addr, channel);
+                    log::info!("{t_ms} ms; addr {addr}; input {source}; {e}");
+                    trace!("addr {}; input {}; no value", addr, source);
                 }
+                ReadingResult::Fault(fault) => {
+                    log::warn!("{t_ms} ms; addr {addr}; input {source}; {fault}");
+                    warn!("addr {}; input {}; {}", addr, source, fault);
+                }
+            }
+
+            if health != Health::Good {
+                warn!("addr {}; input {}; {}", addr, source, health);
             }
 
             trace!("waiting to lock program...");
@@ -113,12 +174,14 @@ impl<'a> Regulator<'a> {
 
             match state {
                 ProgramState::Stopped => {
-                    let needs_water = match result {
-                        ReadingResult::Ok(value) => value < config.low_threshold,
-                        ReadingResult::Err(ValueOutOfRange::Under(_)) => true,
-                        ReadingResult::Err(ValueOutOfRange::Over(_)) => false,
-                        ReadingResult::Err(ValueOutOfRange::None) => false,
-                    };
+                    let needs_water = health == Health::Good
+                        && match result {
+                            ReadingResult::Ok(value) => value < config.low_threshold,
+                            ReadingResult::Err(ValueOutOfRange::Under(_)) => true,
+                            ReadingResult::Err(ValueOutOfRange::Over(_)) => false,
+                            ReadingResult::Err(ValueOutOfRange::None) => false,
+                            ReadingResult::Fault(_) => false,
+                        };
 
                     if needs_water {
                         self.publisher.publish(Action::RunWater(reading)).await;
@@ -126,12 +189,14 @@ impl<'a> Regulator<'a> {
                     }
                 }
                 ProgramState::DoingRuns { .. } => {
-                    let needs_water = match result {
-                        ReadingResult::Ok(value) => value < config.high_threshold,
-                        ReadingResult::Err(ValueOutOfRange::Under(_)) => true,
-                        ReadingResult::Err(ValueOutOfRange::Over(_)) => false,
-                        ReadingResult::Err(ValueOutOfRange::None) => false,
-                    };
+                    let needs_water = health == Health::Good
+                        && match result {
+                            ReadingResult::Ok(value) => value < config.high_threshold,
+                            ReadingResult::Err(ValueOutOfRange::Under(_)) => true,
+                            ReadingResult::Err(ValueOutOfRange::Over(_)) => false,
+                            ReadingResult::Err(ValueOutOfRange::None) => false,
+                            ReadingResult::Fault(_) => false,
+                        };
 
                     if !needs_water {
                         self.publisher.publish(Action::Stop).await;
@@ -141,15 +206,63 @@ impl<'a> Regulator<'a> {
                     *state = ProgramState::DoingRuns { result };
                 }
                 ProgramState::Faulted { fault } => {
-                    warn!("program is halted due to a fault: {}", fault);
-                    self.rgb.signal(Color {
-                        hue: Srgb::<f32>::from(named::RED).get_hue(),
-                        value: 0.1,
-                    });
-
-                    future::pending().await
+                    let fault = *fault;
+                    drop(program);
+                    self.halt(fault).await;
                 }
             }
         }
     }
+
+    fn log_supply(reading: &Reading) {
+        let t_ms = Instant::now().as_millis();
+
+        match *reading {
+            Reading::Flow {
+                milliliters_per_minute,
+                milliliters,
+            } => {
+                log::info!(
+                    "{t_ms} ms; flow {milliliters_per_minute} ml/min; total {milliliters} ml"
+                );
+                trace!(
+                    "flow {} ml/min; total {} ml",
+                    milliliters_per_minute,
+                    milliliters
+                );
+            }
+            Reading::Reservoir(ReadingResult::Ok(value)) => {
+                log::info!("{t_ms} ms; reservoir; value {value}");
+                trace!("reservoir; value {}", value);
+            }
+            Reading::Reservoir(ReadingResult::Err(e)) => {
+                log::info!("{t_ms} ms; reservoir; {e}");
+                trace!("reservoir; no value");
+            }
+            Reading::Reservoir(ReadingResult::Fault(fault)) => {
+                log::warn!("{t_ms} ms; reservoir; {fault}");
+                warn!("reservoir; {}", fault);
+            }
+            _ => {}
+        }
+    }
+
+    async fn halt(&mut self, fault: ProgramFault) -> ! {
+        use rgb::Mode::Color;
+
+        warn!("program is halted due to a fault: {}", fault);
+        self.rgb.signal(Color {
+            hue: Srgb::<f32>::from(named::RED).get_hue(),
+            value: 0.1,
+        });
+
+        // Keep draining readings so the converters publishing them never block
+        // and get reset by the watchdog, which would clear the fault.
+        loop {
+            self.heartbeat.check_in();
+
+            let future = self.subscriber.next_message_pure();
+            let _ = with_timeout(watchdog::CHECK_IN, future).await;
+        }
+    }
 }
@@ -2,35 +2,68 @@ use core::cmp;
 
 use embassy_sync::blocking_mutex::raw::CriticalSectionRawMutex;
 use embassy_sync::pubsub::{PubSubChannel, Publisher, Subscriber};
+use fixed::types::I8F24;
 
-use crate::control;
+use crate::health::Health;
 use crate::scaling::ValueOutOfRange;
+use crate::{adc, control};
 
 #[derive(Clone, Copy)]
 pub enum ReadingResult<T> {
     Ok(T),
     Err(ValueOutOfRange),
+    Fault(adc::Fault),
+}
+
+#[derive(Clone, Copy)]
+pub struct Diagnostic {
+    pub code: i16,
+    pub voltage: I8F24,
+    pub ratio: Option<I8F24>,
+    pub filtered: Option<I8F24>,
+    pub samples: u32,
+    pub spread_percent: I8F24,
 }
 
 #[derive(Clone)]
 pub enum Reading<'a> {
-    Moisture(&'a control::Loop<'a>, ReadingResult<i32>),
+    Moisture(&'a control::Loop<'a>, ReadingResult<i32>, Health),
     Temperature(f32),
+    Flow {
+        milliliters_per_minute: u32,
+        milliliters: u32,
+    },
+    Reservoir(ReadingResult<i32>),
+    Diagnostic(&'a control::Loop<'a>, Diagnostic),
 }
 
-pub type ReadingPublisher<'a> = Publisher<'a, CriticalSectionRawMutex, Reading<'a>, 1, 2, 4>;
-pub type ReadingSubscriber<'a> = Subscriber<'a, CriticalSectionRawMutex, Reading<'a>, 1, 2, 4>;
-pub type ReadingPubSubChannel<'a> = PubSubChannel<CriticalSectionRawMutex, Reading<'a>, 1, 2, 4>;
+pub type ReadingPublisher<'a> = Publisher<'a, CriticalSectionRawMutex, Reading<'a>, 1, 3, 5>;
+pub type ReadingSubscriber<'a> = Subscriber<'a, CriticalSectionRawMutex, Reading<'a>, 1, 3, 5>;
+pub type ReadingPubSubChannel<'a> = PubSubChannel<CriticalSectionRawMutex, Reading<'a>, 1, 3, 5>;
+
+pub type SupplyPublisher<'a> = Publisher<'a, CriticalSectionRawMutex, Reading<'a>, 4, 2, 2>;
+pub type SupplySubscriber<'a> = Subscriber<'a, CriticalSectionRawMutex, Reading<'a>, 4, 2, 2>;
+pub type SupplyPubSubChannel<'a> = PubSubChannel<CriticalSectionRawMutex, Reading<'a>, 4, 2, 2>;
+
+impl<T> From<Result<T, ValueOutOfRange>> for ReadingResult<T> {
+    fn from(value: Result<T, ValueOutOfRange>) -> Self {
+        match value {
+            Ok(value) => Self::Ok(value),
+            Err(e) => Self::Err(e),
+        }
+    }
+}
 
 impl<T: PartialOrd> PartialEq for ReadingResult<T> {
     fn eq(&self, other: &Self) -> bool {
-        use ReadingResult::{Err, Ok};
+        use ReadingResult::{Err, Fault, Ok};
 
         match (self, other) {
             (Ok(value), Ok(other)) => value.eq(other),
             (Err(ValueOutOfRange::Under(min1)), Err(ValueOutOfRange::Under(min2))) => min1 == min2,
             (Err(ValueOutOfRange::Over(max1)), Err(ValueOutOfRange::Over(max2))) => max1 == max2,
             (Err(ValueOutOfRange::None), Err(ValueOutOfRange::None)) => true,
+            (Fault(fault1), Fault(fault2)) => fault1 == fault2,
             _ => false,
         }
     }
@@ -39,7 +72,7 @@ impl<T: PartialOrd> PartialEq for ReadingResult<T> {
 impl<T: PartialOrd> PartialOrd for ReadingResult<T> {
     fn partial_cmp(&self, other: &Self) -> Option<cmp::Ordering> {
         use cmp::Ordering::{Equal, Greater, Less};
-        use ReadingResult::{Err, Ok};
+        use ReadingResult::{Err, Fault, Ok};
 
         match self {
             Ok(value) => match other {
@@ -47,6 +80,7 @@ impl<T: PartialOrd> PartialOrd for ReadingResult<T> {
                 Err(ValueOutOfRange::Under(_)) => Some(Greater),
                 Err(ValueOutOfRange::Over(_)) => Some(Less),
                 Err(ValueOutOfRange::None) => None,
+                Fault(_) => None,
             },
             Err(ValueOutOfRange::Under(min1)) => match other {
                 Ok(_) => Some(Less),
@@ -54,6 +88,7 @@ impl<T: PartialOrd> PartialOrd for ReadingResult<T> {
                 Err(ValueOutOfRange::Under(_)) => None,
                 Err(ValueOutOfRange::Over(_)) => Some(Less),
                 Err(ValueOutOfRange::None) => None,
+                Fault(_) => None,
             },
             Err(ValueOutOfRange::Over(max1)) => match other {
                 Ok(_) => Some(Greater),
@@ -61,8 +96,10 @@ impl<T: PartialOrd> PartialOrd for ReadingResult<T> {
                 Err(ValueOutOfRange::Over(max2)) if max1 == max2 => Some(Equal),
                 Err(ValueOutOfRange::Over(_)) => None,
                 Err(ValueOutOfRange::None) => None,
+                Fault(_) => None,
             },
             Err(ValueOutOfRange::None) => None,
+            Fault(_) => None,
         }
     }
 }
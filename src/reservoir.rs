@@ -0,0 +1,150 @@
+use core::ptr;
+
+use defmt::{debug, warn};
+use embassy_futures::select::select;
+use embassy_rp::gpio::{Input, Level, Output};
+use embassy_time::{Duration, Timer};
+
+use crate::control;
+use crate::health::Health;
+use crate::lockout::{self, Lockout};
+use crate::reading::{Reading, ReadingResult, ReadingSubscriber, SupplyPublisher};
+use crate::scaling::ValueOutOfRange;
+
+pub enum Source<'a> {
+    Float(Input<'a>, Level),
+    Analog(&'a control::Loop<'a>, ReadingSubscriber<'a>),
+}
+
+pub struct Thresholds {
+    minimum: i32,
+    resume: i32,
+    refill_start: i32,
+    refill_stop: i32,
+}
+
+pub struct Monitor<'a> {
+    source: Source<'a>,
+    thresholds: Thresholds,
+    refill: Option<Output<'a>>,
+    lockout: &'a Lockout,
+    publisher: SupplyPublisher<'a>,
+}
+
+impl Thresholds {
+    pub const fn new(minimum: i32, resume: i32, refill_start: i32, refill_stop: i32) -> Self {
+        assert!(resume > minimum, "resume level must be above the minimum");
+        assert!(
+            refill_stop > refill_start,
+            "refill must stop above where it starts"
+        );
+
+        Self {
+            minimum,
+            resume,
+            refill_start,
+            refill_stop,
+        }
+    }
+}
+
+impl<'a> Monitor<'a> {
+    pub fn new(
+        source: Source<'a>,
+        thresholds: Thresholds,
+        refill: Option<Output<'a>>,
+        lockout: &'a Lockout,
+        publisher: SupplyPublisher<'a>,
+    ) -> Self {
+        Self {
+            source,
+            thresholds,
+            refill,
+            lockout,
+            publisher,
+        }
+    }
+
+    pub async fn run(mut self) -> ! {
+        const DEBOUNCE: Duration = Duration::from_millis(500);
+        const PERIOD: Duration = Duration::from_secs(10);
+
+        let mut result = None;
+
+        loop {
+            let new_result = match self.source {
+                Source::Float(ref input, wet) => {
+                    ReadingResult::Ok(if input.get_level() == wet { 100 } else { 0 })
+                }
+                Source::Analog(level_loop, ref mut subscriber) => {
+                    let reading = subscriber.next_message_pure().await;
+                    let Reading::Moisture(control_loop, result, health) = reading else {
+                        continue;
+                    };
+
+                    if !ptr::eq(control_loop, level_loop) {
+                        continue;
+                    }
+
+                    if health != Health::Good {
+                        ReadingResult::Err(ValueOutOfRange::None)
+                    } else {
+                        result
+                    }
+                }
+            };
+
+            self.update(new_result);
+
+            if !result.replace(new_result).is_some_and(|r| r == new_result) {
+                self.publisher
+                    .publish_immediate(Reading::Reservoir(new_result));
+            }
+
+            if let Source::Float(ref mut input, _) = self.source {
+                select(input.wait_for_any_edge(), Timer::after(PERIOD)).await;
+                Timer::after(DEBOUNCE).await;
+            }
+        }
+    }
+
+    fn update(&mut self, result: ReadingResult<i32>) {
+        let level = match result {
+            ReadingResult::Ok(level) => level,
+            ReadingResult::Err(ValueOutOfRange::Under(_)) => 0,
+            ReadingResult::Err(ValueOutOfRange::Over(_)) => 100,
+            ReadingResult::Err(ValueOutOfRange::None) | ReadingResult::Fault(_) => {
+                warn!("reservoir level is unknown");
+                self.lockout.engage(lockout::Reason::Reservoir);
+                self.set_refill(false);
+                return;
+            }
+        };
+
+        if level < self.thresholds.minimum {
+            if !self.lockout.is_engaged(lockout::Reason::Reservoir) {
+                warn!("reservoir level {} is below minimum", level);
+            }
+            self.lockout.engage(lockout::Reason::Reservoir);
+        } else if level >= self.thresholds.resume {
+            self.lockout.release(lockout::Reason::Reservoir);
+        }
+
+        if level <= self.thresholds.refill_start {
+            self.set_refill(true);
+        } else if level >= self.thresholds.refill_stop {
+            self.set_refill(false);
+        }
+    }
+
+    fn set_refill(&mut self, on: bool) {
+        let Some(ref mut refill) = self.refill else {
+            return;
+        };
+
+        if refill.is_set_high() != on {
+            debug!("turning refill {}", if on { "on" } else { "off" });
+            refill.set_level(on.into());
+        }
+    }
+}
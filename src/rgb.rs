@@ -1,5 +1,7 @@
+use core::future;
+
 use embassy_futures::select::select;
-use embassy_futures::select::Either::First;
+use embassy_futures::select::Either::{First, Second};
 use embassy_rp::pio::Instance;
 use embassy_sync::blocking_mutex::raw::CriticalSectionRawMutex;
 use embassy_sync::signal::Signal;
@@ -9,6 +11,7 @@ use palette::{DarkenAssign, Hsv, LightenAssign, RgbHue, ShiftHueAssign, Srgb};
 
 use crate::dev::ws2812::Ws2812;
 
+#[derive(Clone, Copy)]
 pub enum Mode {
     Off,
     White {
@@ -31,94 +34,91 @@ pub enum Mode {
 
 pub struct Control<'d, P: Instance, const S: usize> {
     signal: &'d Signal<CriticalSectionRawMutex, Mode>,
+    alarm: &'d Signal<CriticalSectionRawMutex, Option<Mode>>,
     ws2812: Ws2812<'d, P, S>,
 }
 
 impl<'d, P: Instance, const S: usize> Control<'d, P, S> {
     pub fn new(
         signal: &'d Signal<CriticalSectionRawMutex, Mode>,
+        alarm: &'d Signal<CriticalSectionRawMutex, Option<Mode>>,
         ws2812: Ws2812<'d, P, S>,
     ) -> Self {
-        Self { signal, ws2812 }
+        Self {
+            signal,
+            alarm,
+            ws2812,
+        }
     }
 
     pub async fn run(mut self) -> ! {
-        const CYCLE_DIVS: u16 = 360;
+        let mut mode = Mode::Off;
+        let mut alarm = None;
 
         loop {
-            use Mode::{Breathe, Color, Off, Rainbow, White};
+            let shown = alarm.unwrap_or(mode);
+            let next = select(self.signal.wait(), self.alarm.wait());
+
+            match select(Self::show(&mut self.ws2812, shown), next).await {
+                First(never) => match never {},
+                Second(First(new)) => mode = new,
+                Second(Second(new)) => alarm = new,
+            }
+        }
+    }
 
-            let mode = self.signal.wait().await;
+    async fn show(ws2812: &mut Ws2812<'d, P, S>, mode: Mode) -> ! {
+        use Mode::{Breathe, Color, Off, Rainbow, White};
 
-            let mut color = match mode {
-                Off => Hsv::new(0.0, 0.0, 0.0),
-                White { value } => Hsv::new(0.0, 0.0, value),
-                Color { hue, value } => Hsv::new(hue, 1.0, value),
-                Rainbow { value, .. } => Hsv::new(0.0, 1.0, value),
-                Breathe { hue, .. } => Hsv::new(hue, 1.0, 0.0),
-            };
+        const CYCLE_DIVS: u16 = 360;
 
-            let mut colors = [Srgb::from_color_unclamped(color).into()];
+        let mut color = match mode {
+            Off => Hsv::new(0.0, 0.0, 0.0),
+            White { value } => Hsv::new(0.0, 0.0, value),
+            Color { hue, value } => Hsv::new(hue, 1.0, value),
+            Rainbow { value, .. } => Hsv::new(0.0, 1.0, value),
+            Breathe { hue, .. } => Hsv::new(hue, 1.0, 0.0),
+        };
 
-            match mode {
-                Off | White { .. } | Color { .. } => {
-                    self.ws2812.write_mut(&mut colors).await;
-                    continue;
+        let mut colors = [Srgb::from_color_unclamped(color).into()];
+
+        match mode {
+            Off | White { .. } | Color { .. } => {
+                ws2812.write_mut(&mut colors).await;
+                future::pending().await
+            }
+            Rainbow { period, .. } => {
+                let delta_t = period / u32::from(CYCLE_DIVS);
+                let delta_hue = 360.0 / f32::from(CYCLE_DIVS);
+
+                loop {
+                    ws2812.write_mut(&mut colors).await;
+                    Timer::after(delta_t).await;
+
+                    color.shift_hue_assign(delta_hue);
+                    colors[0] = Srgb::from_color_unclamped(color).into();
                 }
-                Rainbow { period, .. } => {
-                    let delta_t = period / u32::from(CYCLE_DIVS);
-                    let delta_hue = 360.0 / f32::from(CYCLE_DIVS);
-
-                    loop {
-                        let future = async {
-                            self.ws2812.write_mut(&mut colors).await;
-                            self.signal.wait().await
-                        };
-
-                        if let First(mode) = select(future, Timer::after(delta_t)).await {
-                            self.signal.signal(mode);
-                            break;
-                        }
-
-                        color.shift_hue_assign(delta_hue);
+            }
+            Breathe { period, value, .. } => {
+                let delta_t = period / u32::from(CYCLE_DIVS);
+                let delta_value_a = value / f32::from(CYCLE_DIVS / 2);
+                let delta_value_b = value / f32::from(CYCLE_DIVS / 2 + CYCLE_DIVS % 2);
+
+                loop {
+                    for _ in 0..(CYCLE_DIVS / 2) {
+                        ws2812.write_mut(&mut colors).await;
+                        Timer::after(delta_t).await;
+
+                        color.lighten_fixed_assign(delta_value_a);
                         colors[0] = Srgb::from_color_unclamped(color).into();
                     }
-                }
-                Breathe { period, value, .. } => {
-                    let delta_t = period / u32::from(CYCLE_DIVS);
-                    let delta_value_a = value / f32::from(CYCLE_DIVS / 2);
-                    let delta_value_b = value / f32::from(CYCLE_DIVS / 2 + CYCLE_DIVS % 2);
-
-                    'breathing: loop {
-                        for _ in 0..(CYCLE_DIVS / 2) {
-                            let future = async {
-                                self.ws2812.write_mut(&mut colors).await;
-                                self.signal.wait().await
-                            };
-
-                            if let First(mode) = select(future, Timer::after(delta_t)).await {
-                                self.signal.signal(mode);
-                                break 'breathing;
-                            }
-
-                            color.lighten_fixed_assign(delta_value_a);
-                            colors[0] = Srgb::from_color_unclamped(color).into();
-                        }
-
-                        for _ in 0..(CYCLE_DIVS / 2 + CYCLE_DIVS % 2) {
-                            let future = async {
-                                self.ws2812.write_mut(&mut colors).await;
-                                self.signal.wait().await
-                            };
-
-                            if let First(mode) = select(future, Timer::after(delta_t)).await {
-                                self.signal.signal(mode);
-                                break 'breathing;
-                            }
-
-                            color.darken_fixed_assign(delta_value_b);
-                            colors[0] = Srgb::from_color_unclamped(color).into();
-                        }
+
+                    for _ in 0..(CYCLE_DIVS / 2 + CYCLE_DIVS % 2) {
+                        ws2812.write_mut(&mut colors).await;
+                        Timer::after(delta_t).await;
+
+                        color.darken_fixed_assign(delta_value_b);
+                        colors[0] = Srgb::from_color_unclamped(color).into();
                     }
                 }
             }
@@ -0,0 +1,38 @@
+use embassy_rp::gpio::{Level, Output, Pin};
+use embassy_rp::pac::io::vals::Gpio0ctrlFuncsel;
+use embassy_rp::{into_ref, pac, Peripheral};
+use portable_atomic::{AtomicU32, Ordering};
+
+static OFF_LOW: AtomicU32 = AtomicU32::new(0);
+static OFF_HIGH: AtomicU32 = AtomicU32::new(0);
+
+pub fn output<'d>(pin: impl Peripheral<P = impl Pin> + 'd, off: Level) -> Output<'d> {
+    into_ref!(pin);
+    register(&*pin, off);
+    Output::new(pin, off)
+}
+
+pub fn register(pin: &impl Pin, off: Level) {
+    let bit = 1 << pin.pin();
+    match off {
+        Level::Low => OFF_LOW.fetch_or(bit, Ordering::Relaxed),
+        Level::High => OFF_HIGH.fetch_or(bit, Ordering::Relaxed),
+    };
+}
+
+pub fn enter() {
+    let low = OFF_LOW.load(Ordering::Relaxed);
+    let high = OFF_HIGH.load(Ordering::Relaxed);
+
+    pac::SIO.gpio_out(0).value_clr().write_value(low);
+    pac::SIO.gpio_out(0).value_set().write_value(high);
+    pac::SIO.gpio_oe(0).value_set().write_value(low | high);
+
+    for n in 0..30 {
+        if (low | high) & (1 << n) != 0 {
+            pac::IO_BANK0.gpio(n).ctrl().write(|w| {
+                w.set_funcsel(Gpio0ctrlFuncsel::SIO_0 as _);
+            });
+        }
+    }
+}
@@ -0,0 +1,179 @@
+use defmt::{error, warn, Format};
+use embassy_rp::watchdog;
+use embassy_time::{Duration, Instant, Timer};
+use portable_atomic::{AtomicU64, Ordering};
+
+use crate::safe_state;
+
+pub const CHECK_IN: Duration = Duration::from_secs(5);
+
+pub struct Heartbeat {
+    deadline: Duration,
+    last: AtomicU64,
+}
+
+pub struct Runtime {
+    since: AtomicU64,
+    stopped: AtomicU64,
+}
+
+#[derive(Clone, Copy, PartialEq, Format)]
+pub enum Reason {
+    PumpRuntime,
+    MissedCheckIn(u8),
+}
+
+pub struct Supervisor<'a> {
+    watchdog: watchdog::Watchdog,
+    heartbeats: &'a [Heartbeat],
+    runtime: &'a Runtime,
+    max_pump_on: Duration,
+}
+
+impl Heartbeat {
+    const IDLE: u64 = u64::MAX;
+
+    pub const fn new(deadline: Duration) -> Self {
+        Self {
+            deadline,
+            last: AtomicU64::new(Self::IDLE),
+        }
+    }
+
+    pub fn check_in(&self) {
+        self.last
+            .store(Instant::now().as_ticks(), Ordering::Relaxed);
+    }
+
+    fn overdue(&self, now: Instant) -> bool {
+        match self.last.load(Ordering::Relaxed) {
+            Self::IDLE => false,
+            last => now.saturating_duration_since(Instant::from_ticks(last)) > self.deadline,
+        }
+    }
+}
+
+impl Runtime {
+    const OFF: u64 = u64::MAX;
+
+    pub const fn new() -> Self {
+        Self {
+            since: AtomicU64::new(Self::OFF),
+            stopped: AtomicU64::new(0),
+        }
+    }
+
+    pub fn start(&self) {
+        self.since
+            .store(Instant::now().as_ticks(), Ordering::Relaxed);
+    }
+
+    pub fn stop(&self) {
+        self.stopped
+            .store(Instant::now().as_ticks(), Ordering::Relaxed);
+        self.since.store(Self::OFF, Ordering::Relaxed);
+    }
+
+    pub fn idle_for(&self, now: Instant) -> Option<Duration> {
+        if self.running_for(now).is_some() {
+            return None;
+        }
+
+        let stopped = Instant::from_ticks(self.stopped.load(Ordering::Relaxed));
+        Some(now.saturating_duration_since(stopped))
+    }
+
+    fn running_for(&self, now: Instant) -> Option<Duration> {
+        match self.since.load(Ordering::Relaxed) {
+            Self::OFF => None,
+            since => Some(now.saturating_duration_since(Instant::from_ticks(since))),
+        }
+    }
+}
+
+impl Default for Runtime {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Reason {
+    const MAGIC: u32 = 0x5afe_0000;
+
+    fn to_bits(self) -> u32 {
+        match self {
+            Self::PumpRuntime => Self::MAGIC | 0x0100,
+            Self::MissedCheckIn(index) => Self::MAGIC | 0x0200 | u32::from(index),
+        }
+    }
+
+    fn from_bits(bits: u32) -> Option<Self> {
+        if bits & 0xffff_0000 != Self::MAGIC {
+            return None;
+        }
+
+        match bits & 0xff00 {
+            0x0100 => Some(Self::PumpRuntime),
+            0x0200 => Some(Self::MissedCheckIn(bits as u8)),
+            _ => None,
+        }
+    }
+}
+
+impl<'a> Supervisor<'a> {
+    const TIMEOUT: Duration = Duration::from_secs(5);
+    const FEED: Duration = Duration::from_secs(1);
+
+    pub fn new(
+        watchdog: watchdog::Watchdog,
+        heartbeats: &'a [Heartbeat],
+        runtime: &'a Runtime,
+        max_pump_on: Duration,
+    ) -> Self {
+        Self {
+            watchdog,
+            heartbeats,
+            runtime,
+            max_pump_on,
+        }
+    }
+
+    pub async fn run(mut self) -> ! {
+        if let Some(reason) = Reason::from_bits(self.watchdog.get_scratch(0)) {
+            warn!("last reset was forced by the watchdog: {}", reason);
+            self.watchdog.set_scratch(0, 0);
+        }
+
+        self.watchdog.start(Self::TIMEOUT);
+
+        loop {
+            Timer::after(Self::FEED).await;
+            let now = Instant::now();
+
+            if let Some(on) = self.runtime.running_for(now) {
+                if on > self.max_pump_on {
+                    error!(
+                        "pump has been on for {} s; forcing outputs off",
+                        on.as_secs()
+                    );
+                    self.fail(Reason::PumpRuntime);
+                    self.watchdog.trigger_reset();
+                }
+            }
+
+            let overdue = self.heartbeats.iter().position(|h| h.overdue(now));
+            if let Some(index) = overdue {
+                error!("task {} missed its check-in; starving watchdog", index);
+                self.fail(Reason::MissedCheckIn(index as u8));
+                continue;
+            }
+
+            self.watchdog.feed();
+        }
+    }
+
+    fn fail(&mut self, reason: Reason) {
+        safe_state::enter();
+        self.watchdog.set_scratch(0, reason.to_bits());
+    }
+}